@@ -6,7 +6,7 @@ fn main() {
     let inputs: Vec<_> = (0..5).map(|_| InputItem::new((128, 128))).collect();
 
     let packer = SimplePacker::new().max_size((256, 256));
-    let result = packer.pack(inputs);
+    let result = packer.pack(inputs).expect("items should fit");
 
     println!("Pack result: {:#?}", result);
 }
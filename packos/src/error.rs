@@ -0,0 +1,62 @@
+use std::{error::Error, fmt};
+
+use crate::id::Id;
+
+/// The reasons that packing can fail.
+///
+/// Most of these describe a `SimplePacker` that was configured in a way that
+/// can't produce a solution, no matter which items are given to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackError {
+    /// The packer's minimum bucket size is larger than its maximum bucket size
+    /// in at least one dimension.
+    MinSizeLargerThanMaxSize {
+        min_size: (u32, u32),
+        max_size: (u32, u32),
+    },
+
+    /// The packer's padding leaves no room for items in a bucket of the
+    /// maximum size.
+    PaddingTooLarge { padding: u32, max_size: (u32, u32) },
+
+    /// An item has zero width or height. Such items have no sensible place to
+    /// go, so they're rejected instead of being packed.
+    ZeroSizeItem { id: Id, size: (u32, u32) },
+
+    /// An item doesn't fit in an otherwise empty bucket of the maximum size,
+    /// once padding and reserved regions are taken into account.
+    ItemDoesNotFit {
+        id: Id,
+        size: (u32, u32),
+        max_size: (u32, u32),
+    },
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackError::MinSizeLargerThanMaxSize { min_size, max_size } => write!(
+                formatter,
+                "minimum bucket size {}x{} is larger than the maximum bucket size {}x{}",
+                min_size.0, min_size.1, max_size.0, max_size.1
+            ),
+            PackError::PaddingTooLarge { padding, max_size } => write!(
+                formatter,
+                "padding of {} leaves no room for items in buckets of at most {}x{}",
+                padding, max_size.0, max_size.1
+            ),
+            PackError::ZeroSizeItem { size, .. } => write!(
+                formatter,
+                "an item of size {}x{} has no area and cannot be packed",
+                size.0, size.1
+            ),
+            PackError::ItemDoesNotFit { size, max_size, .. } => write!(
+                formatter,
+                "an item of size {}x{} does not fit in buckets of at most {}x{}",
+                size.0, size.1, max_size.0, max_size.1
+            ),
+        }
+    }
+}
+
+impl Error for PackError {}
@@ -3,10 +3,12 @@ use std::{
     sync::atomic::{AtomicUsize, Ordering},
 };
 
+use crate::types::InputItem;
+
 static LAST_ID: AtomicUsize = AtomicUsize::new(1);
 
 /// Represents an item tracked by Packos.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Id(NonZeroUsize);
 
 impl Id {
@@ -14,4 +16,102 @@ impl Id {
         let id = LAST_ID.fetch_add(1, Ordering::SeqCst);
         Id(NonZeroUsize::new(id).unwrap())
     }
+
+    /// The number behind this ID.
+    #[inline]
+    pub fn get(&self) -> usize {
+        self.0.get()
+    }
+}
+
+/// Hands out IDs from its own contiguous range instead of the global counter
+/// that `InputItem::new` uses, so that the IDs of a packing session are
+/// predictable no matter what else has been packed in the process.
+///
+/// IDs from an allocator can repeat IDs from the global counter or from other
+/// allocators, so items from different sources shouldn't be packed together.
+#[derive(Debug)]
+pub struct IdAllocator {
+    next: AtomicUsize,
+}
+
+impl IdAllocator {
+    /// Creates an allocator whose first ID is 1.
+    pub fn new() -> Self {
+        Self::starting_at(NonZeroUsize::new(1).unwrap())
+    }
+
+    /// Creates an allocator whose first ID is `base`.
+    pub fn starting_at(base: NonZeroUsize) -> Self {
+        Self {
+            next: AtomicUsize::new(base.get()),
+        }
+    }
+
+    /// Returns the next ID in this allocator's range. Safe to call from many
+    /// threads at once, though which thread gets which ID is then up to
+    /// scheduling.
+    pub fn next_id(&self) -> Id {
+        let id = self.next.fetch_add(1, Ordering::SeqCst);
+        Id(NonZeroUsize::new(id).expect("IdAllocator ran out of IDs"))
+    }
+
+    /// Creates an `InputItem` with the given size and the next ID in this
+    /// allocator's range.
+    pub fn item(&self, size: (u32, u32)) -> InputItem {
+        InputItem {
+            id: self.next_id(),
+            size,
+            padding: None,
+        }
+    }
+}
+
+impl Default for IdAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::SimplePacker;
+
+    #[test]
+    fn allocators_are_contiguous_and_independent() {
+        let first = IdAllocator::starting_at(NonZeroUsize::new(10).unwrap());
+        let second = IdAllocator::starting_at(NonZeroUsize::new(10).unwrap());
+
+        let ids: Vec<_> = (0..3).map(|_| first.next_id().get()).collect();
+        assert_eq!(ids, vec![10, 11, 12]);
+
+        // Global IDs don't advance an allocator's range.
+        InputItem::new((1, 1));
+        assert_eq!(second.next_id().get(), 10);
+    }
+
+    #[test]
+    fn allocated_items_pack_reproducibly() {
+        let pack = || {
+            let allocator = IdAllocator::new();
+            let items: Vec<_> = [(64, 32), (16, 16), (32, 64)]
+                .iter()
+                .map(|&size| allocator.item(size))
+                .collect();
+
+            let output = SimplePacker::new().pack(items).unwrap();
+            let mut placements: Vec<_> = output
+                .buckets()
+                .iter()
+                .flat_map(|bucket| bucket.items().iter())
+                .map(|item| (item.id().get(), item.position()))
+                .collect();
+            placements.sort_unstable();
+            placements
+        };
+
+        assert_eq!(pack(), pack());
+    }
 }
@@ -23,17 +23,26 @@
 //!
 //! // Compute a solution.
 //! // SimplePacker::pack accepts anything that can turn into an iterator of
-//! // InputItem or &InputItem.
-//! let output = packer.pack(my_items);
+//! // InputItem or &InputItem. Packing fails if the packer is misconfigured or
+//! // an item can't fit in a bucket of the maximum size.
+//! let output = packer.pack(my_items).expect("items should fit");
 //! ```
 //!
+//! Every `InputItem::new` gets an ID from a counter shared by the whole
+//! process. To get the same IDs every time a set of items is packed, like in
+//! tests or batch jobs, create items through an [`IdAllocator`][IdAllocator]
+//! instead.
+//!
 //! [SimplePacker]: struct.SimplePacker.html
+//! [IdAllocator]: struct.IdAllocator.html
 
+mod error;
 mod geometry;
 mod id;
 mod packer;
 mod types;
 
+pub use error::*;
 pub use id::*;
 pub use packer::*;
 pub use types::*;
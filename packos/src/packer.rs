@@ -1,8 +1,10 @@
-use std::{borrow::Borrow, cmp::Reverse};
+use std::{borrow::Borrow, cmp::Reverse, collections::HashMap};
 
 use crate::{
+    error::PackError,
     geometry::Rect,
-    types::{Bucket, InputItem, OutputItem, PackOutput},
+    id::Id,
+    types::{Bucket, BucketDiagnostics, InputItem, OutputItem, PackOutput},
 };
 
 /// A configurable rectangle packer using a simple packing algorithm.
@@ -11,6 +13,84 @@ pub struct SimplePacker {
     min_size: (u32, u32),
     max_size: (u32, u32),
     padding: u32,
+    align: u32,
+    assume_sorted: bool,
+    placement: Placement,
+    reserved: Vec<Rect>,
+    max_items_per_bucket: Option<usize>,
+    collect_diagnostics: bool,
+}
+
+/// Decides which position `SimplePacker` places each item at when more than
+/// one position would fit it.
+///
+/// Positions are always chosen from the corners left behind by items that
+/// have already been placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Placement {
+    /// Use the first position that fits, in the order positions were created.
+    #[default]
+    FirstFit,
+
+    /// Use the position closest to the top of the bucket, then the one
+    /// closest to the left. This is the classic bottom-left heuristic with the
+    /// Y axis pointing down, like it does in images.
+    BottomLeft,
+
+    /// Use the position that leaves the least space on the shorter side of
+    /// the item before it runs into another item or the edge of the bucket.
+    BestShortSideFit,
+}
+
+impl Placement {
+    /// Picks the index of the anchor to place an item at out of the anchors
+    /// where the item fits.
+    fn choose(
+        self,
+        candidates: impl Iterator<Item = (usize, Rect)>,
+        occupied: &[Rect],
+        bucket_size: (u32, u32),
+    ) -> Option<usize> {
+        match self {
+            Placement::FirstFit => candidates.map(|(index, _)| index).next(),
+            Placement::BottomLeft => candidates
+                .min_by_key(|(_, rect)| (rect.pos.1, rect.pos.0))
+                .map(|(index, _)| index),
+            Placement::BestShortSideFit => candidates
+                .min_by_key(|(_, rect)| {
+                    let (leftover_x, leftover_y) = leftover_space(rect, occupied, bucket_size);
+                    let short_side = leftover_x.min(leftover_y);
+                    let long_side = leftover_x.max(leftover_y);
+
+                    (short_side, long_side, rect.pos.1, rect.pos.0)
+                })
+                .map(|(index, _)| index),
+        }
+    }
+}
+
+/// Measures how much free space there is to the right of and below the given
+/// rect before it runs into another item or the edge of the bucket.
+fn leftover_space(rect: &Rect, occupied: &[Rect], bucket_size: (u32, u32)) -> (u32, u32) {
+    let max = rect.max();
+    let mut free_max = bucket_size;
+
+    for other in occupied {
+        let other_max = other.max();
+
+        let overlaps_x = other.pos.0 < max.0 && other_max.0 > rect.pos.0;
+        let overlaps_y = other.pos.1 < max.1 && other_max.1 > rect.pos.1;
+
+        if overlaps_y && other.pos.0 >= max.0 {
+            free_max.0 = free_max.0.min(other.pos.0);
+        }
+
+        if overlaps_x && other.pos.1 >= max.1 {
+            free_max.1 = free_max.1.min(other.pos.1);
+        }
+    }
+
+    (free_max.0 - max.0, free_max.1 - max.1)
 }
 
 impl Default for SimplePacker {
@@ -24,11 +104,23 @@ impl SimplePacker {
     /// * `min_size` of 128x128
     /// * `max_size` of 1024x1024
     /// * `padding` of 0
+    /// * `align` of 1
+    /// * `assume_sorted` of false
+    /// * `placement` of `Placement::FirstFit`
+    /// * no reserved regions
+    /// * no limit on the number of items per bucket
+    /// * `collect_diagnostics` of false
     pub fn new() -> Self {
         Self {
             min_size: (128, 128),
             max_size: (1024, 1024),
             padding: 0,
+            align: 1,
+            assume_sorted: false,
+            placement: Placement::FirstFit,
+            reserved: Vec::new(),
+            max_items_per_bucket: None,
+            collect_diagnostics: false,
         }
     }
 
@@ -44,22 +136,113 @@ impl SimplePacker {
         Self { padding, ..self }
     }
 
+    /// Makes every item's position a multiple of the given number of pixels,
+    /// for atlases that are sampled in blocks. Items that don't fit in a
+    /// bucket once aligned are moved to the next one. An alignment of 0 is
+    /// treated as 1.
+    pub fn align(self, align: u32) -> Self {
+        Self {
+            align: align.max(1),
+            ..self
+        }
+    }
+
+    /// Tells the packer that inputs are already sorted by area in descending
+    /// order, largest first, so that it can skip sorting them.
+    ///
+    /// Inputs that are not sorted this way will still be packed, but usually
+    /// less tightly.
+    pub fn assume_sorted(self, assume_sorted: bool) -> Self {
+        Self {
+            assume_sorted,
+            ..self
+        }
+    }
+
+    /// Sets the heuristic used to decide where each item is placed.
+    pub fn placement(self, placement: Placement) -> Self {
+        Self { placement, ..self }
+    }
+
+    /// Keeps the given region free in every bucket, so that items are packed
+    /// around it. Can be called more than once to reserve several regions.
+    ///
+    /// Padding is kept between reserved regions and items just like it is
+    /// between items.
+    pub fn reserve(mut self, pos: (u32, u32), size: (u32, u32)) -> Self {
+        self.reserved.push(Rect { pos, size });
+        self
+    }
+
+    /// Limits how many items can be packed into each bucket, for consumers
+    /// that can only index so many items per bucket. Once a bucket is full,
+    /// the remaining items go into new buckets even if there's room left. A
+    /// limit of 0 is treated as 1.
+    pub fn max_items_per_bucket(self, max_items: usize) -> Self {
+        Self {
+            max_items_per_bucket: Some(max_items.max(1)),
+            ..self
+        }
+    }
+
+    /// Records how each bucket was packed, like how many times it grew, which
+    /// is available from `Bucket::diagnostics` afterwards.
+    pub fn collect_diagnostics(self, collect_diagnostics: bool) -> Self {
+        Self {
+            collect_diagnostics,
+            ..self
+        }
+    }
+
     /// Pack a group of input rectangles into zero or more buckets.
     ///
     /// Accepts any type that can turn into an iterator of anything that can
     /// borrow as an `InputItem`. This helps make sure that types like
     /// `Vec<InputItem>`, `&[InputItem]`, and iterators that return either
     /// `InputItem` or `&InputItem` can be valid inputs.
-    pub fn pack<Iter, Item>(&self, items: Iter) -> PackOutput
+    ///
+    /// Fails if the packer's configuration can't produce a solution, or if any
+    /// of the items have zero width or height or are too large to ever fit in
+    /// a bucket.
+    pub fn pack<Iter, Item>(&self, items: Iter) -> Result<PackOutput, PackError>
     where
         Iter: IntoIterator<Item = Item>,
         Item: Borrow<InputItem>,
     {
-        let mut remaining_items: Vec<_> = items.into_iter().map(|item| *item.borrow()).collect();
-        remaining_items.sort_by_key(|input| Reverse(input.area()));
+        self.pack_vec(items.into_iter().map(|item| *item.borrow()).collect())
+    }
+
+    /// Pack an owned list of input rectangles into zero or more buckets.
+    ///
+    /// Unlike `pack`, this reuses the given `Vec` instead of copying it, which
+    /// can be combined with `assume_sorted` to avoid extra work for large sets
+    /// of inputs.
+    pub fn pack_vec(&self, mut remaining_items: Vec<InputItem>) -> Result<PackOutput, PackError> {
+        self.validate()?;
+
+        // Items with no area would be placed on top of other items and leave
+        // useless anchors behind, so we don't accept them at all.
+        if let Some(item) = remaining_items.iter().find(|item| item.area() == 0) {
+            return Err(PackError::ZeroSizeItem {
+                id: item.id,
+                size: item.size,
+            });
+        }
+
+        if !self.assume_sorted {
+            remaining_items.sort_by_key(|input| Reverse(input.area()));
+        }
+
+        // Items are packed with their padding included in their size, and
+        // the padding is taken back off once everything is placed.
+        let paddings: HashMap<Id, Option<u32>> = remaining_items
+            .iter()
+            .map(|item| (item.id, item.padding))
+            .collect();
 
         for item in &mut remaining_items {
-            item.size = (item.size.0 + self.padding, item.size.1 + self.padding);
+            let (before, after) = self.padding_around(item.padding);
+            item.size = (item.size.0 + before + after, item.size.1 + before + after);
         }
 
         let num_items = remaining_items.len();
@@ -71,10 +254,20 @@ impl SimplePacker {
             // TODO: Compute minimum size from total area of remaining images,
             // rounded up to nearest po2 and clamped to max_size.
             let mut current_size = self.min_size;
+            let mut diagnostics = BucketDiagnostics {
+                resizes: 0,
+                resized_for: None,
+                overflowed_with: None,
+            };
 
             loop {
-                let (bucket, next_remaining) =
-                    Self::pack_one_bucket(&remaining_items, current_size);
+                let (mut bucket, next_remaining) =
+                    self.pack_one_bucket(&remaining_items, current_size);
+
+                if self.collect_diagnostics {
+                    diagnostics.overflowed_with = next_remaining.first().map(|item| item.id);
+                    bucket.diagnostics = Some(diagnostics);
+                }
 
                 // If this size was large enough to contain the rest of the
                 // images, we're done packing!
@@ -84,15 +277,39 @@ impl SimplePacker {
                     break;
                 }
 
+                // A bucket that already has as many items as it's allowed
+                // can't take any more, no matter how large it gets.
+                let is_full = self
+                    .max_items_per_bucket
+                    .is_some_and(|max_items| bucket.items.len() >= max_items);
+
                 // Otherwise, we can try to re-pack this set of images into a
                 // larger bucket to try to minimize the total number of buckets
                 // we use.
-                if current_size.0 < self.max_size.0 || current_size.1 < self.max_size.1 {
+                if !is_full
+                    && (current_size.0 < self.max_size.0 || current_size.1 < self.max_size.1)
+                {
                     current_size = (
                         (current_size.0 * 2).min(self.max_size.0),
                         (current_size.1 * 2).min(self.max_size.1),
                     );
+
+                    diagnostics.resizes += 1;
+                    diagnostics.resized_for = Some(next_remaining[0].id);
                 } else {
+                    // If nothing fit into an empty bucket of the max size, the
+                    // remaining items never will.
+                    if bucket.items.is_empty() {
+                        let item = next_remaining[0];
+                        let (before, after) = self.padding_around(item.padding);
+
+                        return Err(PackError::ItemDoesNotFit {
+                            id: item.id,
+                            size: (item.size.0 - before - after, item.size.1 - before - after),
+                            max_size: self.max_size,
+                        });
+                    }
+
                     // We're already at the max bucket size, so this is the
                     // smallest number of buckets we'll get.
                     buckets.push(bucket);
@@ -104,9 +321,12 @@ impl SimplePacker {
 
         for bucket in &mut buckets {
             for item in &mut bucket.items {
+                let (before, after) = self.padding_around(paddings[&item.id]);
+
+                item.rect.pos = (item.rect.pos.0 + before, item.rect.pos.1 + before);
                 item.rect.size = (
-                    item.rect.size.0 - self.padding,
-                    item.rect.size.1 - self.padding,
+                    item.rect.size.0 - before - after,
+                    item.rect.size.1 - before - after,
                 );
             }
         }
@@ -117,10 +337,39 @@ impl SimplePacker {
             buckets.len()
         );
 
-        PackOutput { buckets }
+        Ok(PackOutput { buckets })
+    }
+
+    /// The padding kept before (above and to the left of) and after (below and
+    /// to the right of) an item with the given padding of its own.
+    fn padding_around(&self, item_padding: Option<u32>) -> (u32, u32) {
+        match item_padding {
+            Some(padding) => (padding, padding),
+            None => (0, self.padding),
+        }
+    }
+
+    /// Checks for configurations that can't pack anything.
+    fn validate(&self) -> Result<(), PackError> {
+        if self.min_size.0 > self.max_size.0 || self.min_size.1 > self.max_size.1 {
+            return Err(PackError::MinSizeLargerThanMaxSize {
+                min_size: self.min_size,
+                max_size: self.max_size,
+            });
+        }
+
+        if self.padding >= self.max_size.0 || self.padding >= self.max_size.1 {
+            return Err(PackError::PaddingTooLarge {
+                padding: self.padding,
+                max_size: self.max_size,
+            });
+        }
+
+        Ok(())
     }
 
     fn pack_one_bucket(
+        &self,
         remaining_items: &[InputItem],
         bucket_size: (u32, u32),
     ) -> (Bucket, Vec<InputItem>) {
@@ -130,11 +379,48 @@ impl SimplePacker {
             bucket_size
         );
 
+        let align_up = |value: u32| value.div_ceil(self.align) * self.align;
+        let in_bucket = |anchor: (u32, u32)| anchor.0 < bucket_size.0 && anchor.1 < bucket_size.1;
+
         let mut anchors = vec![(0, 0)];
         let mut items: Vec<OutputItem> = Vec::new();
         let mut unpacked_items = Vec::new();
 
+        // Everything that items can't overlap: reserved regions, followed by
+        // the items placed so far.
+        let mut occupied: Vec<Rect> = Vec::new();
+
+        for reserved in &self.reserved {
+            let padded = Rect {
+                pos: reserved.pos,
+                size: (
+                    reserved.size.0 + self.padding,
+                    reserved.size.1 + self.padding,
+                ),
+            };
+            let max = padded.max();
+
+            anchors.extend(
+                [
+                    (align_up(max.0), padded.pos.1),
+                    (padded.pos.0, align_up(max.1)),
+                ]
+                .iter()
+                .copied()
+                .filter(|&anchor| in_bucket(anchor)),
+            );
+            occupied.push(padded);
+        }
+
         for input_item in remaining_items {
+            if self
+                .max_items_per_bucket
+                .is_some_and(|max_items| items.len() >= max_items)
+            {
+                unpacked_items.push(*input_item);
+                continue;
+            }
+
             log::trace!(
                 "For item {:?} ({}x{}), evaluating these anchors: {:?}",
                 input_item.id(),
@@ -143,34 +429,45 @@ impl SimplePacker {
                 anchors
             );
 
-            let fit_anchor = anchors.iter().copied().position(|anchor| {
-                let potential_rect = Rect {
-                    pos: anchor,
-                    size: input_item.size,
-                };
+            let candidates = anchors
+                .iter()
+                .copied()
+                .enumerate()
+                .map(|(index, anchor)| {
+                    let potential_rect = Rect {
+                        pos: anchor,
+                        size: input_item.size,
+                    };
 
-                let fits_with_others = items
-                    .iter()
-                    .all(|packed_item| !potential_rect.intersects(&packed_item.rect));
+                    (index, potential_rect)
+                })
+                .filter(|(_, potential_rect)| {
+                    let fits_with_others = occupied
+                        .iter()
+                        .all(|other| !potential_rect.intersects(other));
 
-                let max = potential_rect.max();
-                let fits_in_bucket = max.0 < bucket_size.0 && max.1 < bucket_size.1;
+                    let max = potential_rect.max();
+                    let fits_in_bucket = max.0 < bucket_size.0 && max.1 < bucket_size.1;
 
-                fits_with_others && fits_in_bucket
-            });
+                    fits_with_others && fits_in_bucket
+                });
+
+            let fit_anchor = self.placement.choose(candidates, &occupied, bucket_size);
 
             if let Some(index) = fit_anchor {
                 let anchor = anchors.remove(index);
 
                 log::trace!("Fit at anchor {:?}", anchor);
 
-                let new_anchor_hor = (anchor.0 + input_item.size.0, anchor.1);
-                if new_anchor_hor.0 < bucket_size.0 && new_anchor_hor.1 < bucket_size.1 {
+                // Anchors are aligned as they're created, so that every item
+                // placed at one is aligned too.
+                let new_anchor_hor = (align_up(anchor.0 + input_item.size.0), anchor.1);
+                if in_bucket(new_anchor_hor) {
                     anchors.push(new_anchor_hor);
                 }
 
-                let new_anchor_ver = (anchor.0, anchor.1 + input_item.size.1);
-                if new_anchor_ver.0 < bucket_size.0 && new_anchor_ver.1 < bucket_size.1 {
+                let new_anchor_ver = (anchor.0, align_up(anchor.1 + input_item.size.1));
+                if in_bucket(new_anchor_ver) {
                     anchors.push(new_anchor_ver);
                 }
 
@@ -181,6 +478,7 @@ impl SimplePacker {
                         size: input_item.size,
                     },
                 };
+                occupied.push(output_item.rect);
                 items.push(output_item);
             } else {
                 log::trace!("Did not fit in this bucket.");
@@ -192,8 +490,351 @@ impl SimplePacker {
         let bucket = Bucket {
             size: bucket_size,
             items,
+            diagnostics: None,
         };
 
         (bucket, unpacked_items)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mixed_items() -> Vec<InputItem> {
+        let mut items = Vec::new();
+
+        for i in 0..80 {
+            let width = 8 + (i * 37) % 90;
+            let height = 8 + (i * 53) % 70;
+            items.push(InputItem::new((width, height)));
+        }
+
+        items
+    }
+
+    #[test]
+    fn best_short_side_fit_is_no_worse_than_first_fit() {
+        let items = mixed_items();
+        let packer = SimplePacker::new().max_size((256, 256)).padding(1);
+
+        let first_fit = packer
+            .clone()
+            .placement(Placement::FirstFit)
+            .pack(&items)
+            .unwrap();
+        let best_short_side_fit = packer
+            .placement(Placement::BestShortSideFit)
+            .pack(&items)
+            .unwrap();
+
+        assert!(best_short_side_fit.occupancy() >= first_fit.occupancy());
+    }
+
+    #[test]
+    fn aligned_items_have_aligned_positions() {
+        let items = mixed_items();
+        let output = SimplePacker::new()
+            .max_size((256, 256))
+            .padding(1)
+            .align(4)
+            .pack(&items)
+            .unwrap();
+
+        let packed: usize = output
+            .buckets()
+            .iter()
+            .map(|bucket| bucket.items().len())
+            .sum();
+        assert_eq!(packed, items.len());
+
+        for bucket in output.buckets() {
+            for item in bucket.items() {
+                let (x, y) = item.min();
+                assert_eq!(x % 4, 0, "x position {} is not aligned", x);
+                assert_eq!(y % 4, 0, "y position {} is not aligned", y);
+
+                let max = item.max();
+                assert!(max.0 <= bucket.size().0 && max.1 <= bucket.size().1);
+            }
+        }
+    }
+
+    #[test]
+    fn reserved_regions_stay_free() {
+        let items = mixed_items();
+        let output = SimplePacker::new()
+            .max_size((256, 256))
+            .padding(1)
+            .reserve((0, 0), (32, 32))
+            .pack(&items)
+            .unwrap();
+
+        let packed: usize = output
+            .buckets()
+            .iter()
+            .map(|bucket| bucket.items().len())
+            .sum();
+        assert_eq!(packed, items.len());
+
+        let reserved = Rect {
+            pos: (0, 0),
+            size: (32, 32),
+        };
+
+        for bucket in output.buckets() {
+            for item in bucket.items() {
+                assert!(
+                    !item.rect.intersects(&reserved),
+                    "item at {:?} overlaps the reserved region",
+                    item.min()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn max_items_per_bucket_spills_into_new_buckets() {
+        let items: Vec<_> = (0..10).map(|_| InputItem::new((4, 4))).collect();
+        let output = SimplePacker::new()
+            .max_items_per_bucket(4)
+            .pack(&items)
+            .unwrap();
+
+        assert!(output.buckets().len() >= 3);
+
+        let mut packed = 0;
+        for bucket in output.buckets() {
+            assert!(bucket.items().len() <= 4);
+            packed += bucket.items().len();
+        }
+        assert_eq!(packed, items.len());
+    }
+
+    #[test]
+    fn sorted_buckets_have_stable_order() {
+        let items = mixed_items();
+        let packer = SimplePacker::new().max_size((128, 128)).padding(1);
+
+        let layout = |output: &PackOutput| -> Vec<_> {
+            output
+                .buckets_sorted()
+                .map(|bucket| {
+                    let items: Vec<_> = bucket
+                        .items()
+                        .iter()
+                        .map(|item| (item.id(), item.min()))
+                        .collect();
+                    (bucket.size(), items)
+                })
+                .collect()
+        };
+
+        let first = layout(&packer.pack(&items).unwrap());
+        let second = layout(&packer.pack(&items).unwrap());
+
+        assert!(first.len() > 1);
+        assert_eq!(first, second);
+
+        let areas: Vec<_> = first.iter().map(|(size, _)| size.0 * size.1).collect();
+        assert!(areas.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+
+    #[test]
+    fn items_sorted_by_position_are_in_reading_order() {
+        let output = SimplePacker::new().pack(mixed_items()).unwrap();
+
+        for bucket in output.buckets() {
+            let positions: Vec<_> = bucket
+                .items_sorted_by_position()
+                .map(|item| (item.min().1, item.min().0))
+                .collect();
+
+            let mut sorted = positions.clone();
+            sorted.sort();
+
+            assert_eq!(positions.len(), bucket.items().len());
+            assert_eq!(positions, sorted);
+        }
+    }
+
+    #[test]
+    fn items_keep_their_own_padding() {
+        let mut items = mixed_items();
+        for (i, item) in items.iter_mut().enumerate() {
+            match i % 3 {
+                0 => *item = item.with_padding(4),
+                1 => *item = item.with_padding(2),
+                _ => {}
+            }
+        }
+
+        let paddings: HashMap<Id, Option<u32>> = items
+            .iter()
+            .map(|item| (item.id(), item.padding()))
+            .collect();
+        let sizes: HashMap<Id, (u32, u32)> =
+            items.iter().map(|item| (item.id(), item.size())).collect();
+
+        let output = SimplePacker::new()
+            .max_size((256, 256))
+            .padding(1)
+            .pack(&items)
+            .unwrap();
+
+        for bucket in output.buckets() {
+            let items = bucket.items();
+
+            for (i, item) in items.iter().enumerate() {
+                assert_eq!(item.size(), sizes[&item.id()]);
+
+                // Items with their own padding keep it from the bucket's edges
+                // as well as from other items.
+                if let Some(padding) = paddings[&item.id()] {
+                    assert!(item.min().0 >= padding && item.min().1 >= padding);
+                    assert!(item.max().0 + padding <= bucket.size().0);
+                    assert!(item.max().1 + padding <= bucket.size().1);
+                }
+
+                for other in &items[i + 1..] {
+                    let gap_x = other.min().0.saturating_sub(item.max().0)
+                        + item.min().0.saturating_sub(other.max().0);
+                    let gap_y = other.min().1.saturating_sub(item.max().1)
+                        + item.min().1.saturating_sub(other.max().1);
+
+                    let required = paddings[&item.id()]
+                        .unwrap_or(1)
+                        .max(paddings[&other.id()].unwrap_or(1));
+
+                    assert!(
+                        gap_x.max(gap_y) >= required,
+                        "items at {:?} and {:?} are {} apart, but need {}",
+                        item.min(),
+                        other.min(),
+                        gap_x.max(gap_y),
+                        required
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn diagnostics_explain_resizes_and_overflow() {
+        let small = InputItem::new((32, 32));
+        let large = InputItem::new((100, 100));
+        let overflow = InputItem::new((200, 200));
+
+        let output = SimplePacker::new()
+            .min_size((64, 64))
+            .max_size((256, 256))
+            .collect_diagnostics(true)
+            .pack([small, large, overflow])
+            .unwrap();
+
+        let buckets = output.buckets();
+        assert_eq!(buckets.len(), 2);
+
+        // The largest item goes first and fills the first bucket at its
+        // maximum size, so the other items overflow into a second bucket that
+        // grows to fit the next largest of them.
+        let first = buckets[0].diagnostics().unwrap();
+        assert_eq!(first.resizes(), 2);
+        assert_eq!(first.resized_for(), Some(overflow.id()));
+        assert_eq!(first.overflowed_with(), Some(large.id()));
+
+        let second = buckets[1].diagnostics().unwrap();
+        assert_eq!(second.resizes(), 1);
+        assert_eq!(second.resized_for(), Some(large.id()));
+        assert_eq!(second.overflowed_with(), None);
+    }
+
+    #[test]
+    fn diagnostics_are_not_collected_by_default() {
+        let output = SimplePacker::new().pack(mixed_items()).unwrap();
+
+        assert!(output
+            .buckets()
+            .iter()
+            .all(|bucket| bucket.diagnostics().is_none()));
+    }
+
+    #[test]
+    fn invalid_configurations_are_reported() {
+        let items = [InputItem::new((8, 8))];
+
+        assert_eq!(
+            SimplePacker::new()
+                .min_size((512, 512))
+                .max_size((256, 256))
+                .pack(items)
+                .unwrap_err(),
+            PackError::MinSizeLargerThanMaxSize {
+                min_size: (512, 512),
+                max_size: (256, 256),
+            }
+        );
+
+        assert_eq!(
+            SimplePacker::new()
+                .max_size((128, 128))
+                .padding(128)
+                .pack(items)
+                .unwrap_err(),
+            PackError::PaddingTooLarge {
+                padding: 128,
+                max_size: (128, 128),
+            }
+        );
+    }
+
+    #[test]
+    fn items_too_large_to_fit_are_reported() {
+        let too_large = InputItem::new((300, 20));
+        let items = [InputItem::new((8, 8)), too_large];
+
+        let err = SimplePacker::new()
+            .max_size((256, 256))
+            .pack(items)
+            .unwrap_err();
+
+        assert_eq!(
+            err,
+            PackError::ItemDoesNotFit {
+                id: too_large.id(),
+                size: (300, 20),
+                max_size: (256, 256),
+            }
+        );
+    }
+
+    #[test]
+    fn zero_size_items_are_rejected() {
+        for &size in &[(0, 16), (16, 0), (0, 0)] {
+            let degenerate = InputItem::new(size);
+            let items = [InputItem::new((8, 8)), degenerate];
+
+            assert_eq!(
+                SimplePacker::new().pack(items).unwrap_err(),
+                PackError::ZeroSizeItem {
+                    id: degenerate.id(),
+                    size,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn occupancy() {
+        let packer = SimplePacker::new().min_size((16, 16)).max_size((16, 16));
+
+        let empty = packer.pack(Vec::<InputItem>::new()).unwrap();
+        assert_eq!(empty.occupancy(), 0.0);
+
+        let split = packer
+            .pack(vec![InputItem::new((15, 15)), InputItem::new((8, 8))])
+            .unwrap();
+        assert_eq!(split.buckets().len(), 2);
+        assert_eq!(split.occupancy(), (225.0 + 64.0) / 512.0);
+    }
+}
@@ -1,3 +1,5 @@
+use std::cmp::Reverse;
+
 use crate::{geometry::Rect, id::Id};
 
 /// An input to the rectangle packing routines.
@@ -9,6 +11,7 @@ use crate::{geometry::Rect, id::Id};
 pub struct InputItem {
     pub(crate) id: Id,
     pub(crate) size: (u32, u32),
+    pub(crate) padding: Option<u32>,
 }
 
 impl InputItem {
@@ -17,9 +20,31 @@ impl InputItem {
         Self {
             id: Id::new(),
             size,
+            padding: None,
         }
     }
 
+    /// Gives this item its own padding instead of the packer's.
+    ///
+    /// The packer's padding is only kept to the right of and below each item,
+    /// which is enough to keep every item apart. An item's own padding is kept
+    /// on every side of it instead, so that no other item or bucket edge is
+    /// ever closer than that, no matter what's packed next to it. With
+    /// `SimplePacker::align`, it's the padded area that's aligned, so the item
+    /// itself is aligned only if its padding is a multiple of the alignment.
+    #[inline]
+    pub fn with_padding(self, padding: u32) -> Self {
+        Self {
+            padding: Some(padding),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn padding(&self) -> Option<u32> {
+        self.padding
+    }
+
     #[inline]
     pub fn size(&self) -> (u32, u32) {
         self.size
@@ -75,9 +100,8 @@ impl OutputItem {
 
 /// The results from running a packing function.
 ///
-/// Currently only exposes the list of buckets that inputs were grouped into. In
-/// the future, this struct may also have information about inputs that didn't
-/// fit and how efficient the result is.
+/// Exposes the list of buckets that inputs were grouped into, and how
+/// efficiently they were used.
 #[derive(Debug, Clone)]
 pub struct PackOutput {
     pub(crate) buckets: Vec<Bucket>,
@@ -88,6 +112,65 @@ impl PackOutput {
     pub fn buckets(&self) -> &[Bucket] {
         &self.buckets
     }
+
+    /// Iterates over the buckets in a stable order: largest first, with ties
+    /// broken by the smallest item ID in each bucket.
+    ///
+    /// `buckets` returns buckets in the order they were packed, which can
+    /// change when the same items are given in a different order.
+    pub fn buckets_sorted(&self) -> impl Iterator<Item = &Bucket> {
+        let mut buckets: Vec<_> = self.buckets.iter().collect();
+        buckets.sort_by_key(|bucket| {
+            let min_id = bucket.items.iter().map(|item| item.id).min();
+            (Reverse(bucket.size.0 * bucket.size.1), min_id)
+        });
+        buckets.into_iter()
+    }
+
+    /// The fraction of the total area of all buckets that is covered by items,
+    /// from 0 to 1. Returns 0 if there are no buckets.
+    pub fn occupancy(&self) -> f64 {
+        let used: u64 = self.buckets.iter().map(Bucket::used_area).sum();
+        let total: u64 = self.buckets.iter().map(Bucket::area).sum();
+
+        if total == 0 {
+            0.0
+        } else {
+            used as f64 / total as f64
+        }
+    }
+}
+
+/// Describes how a bucket ended up at its size, which helps explain why a set
+/// of items was split across more buckets than expected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BucketDiagnostics {
+    pub(crate) resizes: u32,
+    pub(crate) resized_for: Option<Id>,
+    pub(crate) overflowed_with: Option<Id>,
+}
+
+impl BucketDiagnostics {
+    /// How many times the bucket grew from the packer's minimum size before
+    /// everything fit, or before it reached the maximum size.
+    #[inline]
+    pub fn resizes(&self) -> u32 {
+        self.resizes
+    }
+
+    /// The item that didn't fit at the size the bucket had before its last
+    /// resize, if it was ever resized.
+    #[inline]
+    pub fn resized_for(&self) -> Option<Id> {
+        self.resized_for
+    }
+
+    /// The first item that didn't fit in this bucket and had to go into
+    /// another one, if any.
+    #[inline]
+    pub fn overflowed_with(&self) -> Option<Id> {
+        self.overflowed_with
+    }
 }
 
 /// Contains a set of `OutputItem` values that were packed together into the
@@ -96,6 +179,7 @@ impl PackOutput {
 pub struct Bucket {
     pub(crate) size: (u32, u32),
     pub(crate) items: Vec<OutputItem>,
+    pub(crate) diagnostics: Option<BucketDiagnostics>,
 }
 
 impl Bucket {
@@ -104,8 +188,39 @@ impl Bucket {
         self.size
     }
 
+    /// Details about how this bucket was packed, if the packer was asked to
+    /// collect them with `SimplePacker::collect_diagnostics`.
+    #[inline]
+    pub fn diagnostics(&self) -> Option<&BucketDiagnostics> {
+        self.diagnostics.as_ref()
+    }
+
     #[inline]
     pub fn items(&self) -> &[OutputItem] {
         &self.items
     }
+
+    /// Iterates over the items in this bucket in reading order: top to bottom,
+    /// then left to right.
+    ///
+    /// `items` returns items in the order they were placed, which usually
+    /// isn't useful to show to humans.
+    pub fn items_sorted_by_position(&self) -> impl Iterator<Item = &OutputItem> {
+        let mut items: Vec<_> = self.items.iter().collect();
+        items.sort_by_key(|item| (item.rect.pos.1, item.rect.pos.0));
+        items.into_iter()
+    }
+
+    /// The total area of this bucket.
+    pub fn area(&self) -> u64 {
+        u64::from(self.size.0) * u64::from(self.size.1)
+    }
+
+    /// The area of this bucket that is covered by items.
+    pub fn used_area(&self) -> u64 {
+        self.items
+            .iter()
+            .map(|item| u64::from(item.rect.size.0) * u64::from(item.rect.size.1))
+            .sum()
+    }
 }
@@ -1,12 +1,22 @@
 //! Changes pixels in an image that are totally transparent to the color of
 //! their nearest non-transparent neighbor. This fixes artifacting when images
 //! are resized in some contexts.
+//!
+//! Neighboring colors are averaged as their sRGB values by default. With
+//! `linear_blending`, they're converted to linear light first, which
+//! keeps the bled color between bright and dark neighbors from being too dark.
 
 use std::collections::VecDeque;
 
-use crate::image::{Image, Pixel};
+use crate::image::{linear_to_srgb, srgb_to_linear, Image, Pixel};
+
+pub(crate) fn alpha_bleed(image: &mut Image, linear_blending: bool) {
+    // Either there are no transparent pixels to change, or there are no
+    // colors to bleed into them.
+    if image.is_fully_opaque() || image.is_fully_transparent() {
+        return;
+    }
 
-pub(crate) fn alpha_bleed(image: &mut Image) {
     let (w, h) = image.size();
 
     // Tells whether a given position has been touched by the bleeding algorithm
@@ -27,18 +37,16 @@ pub(crate) fn alpha_bleed(image: &mut Image) {
 
     // An iterator of in-bounds positions adjacent to the given one.
     let adjacent_positions = |x, y| {
-        DIRECTIONS
-            .into_iter()
-            .filter_map(move |(x_offset, y_offset)| {
-                let x_source = (x as i32) + x_offset;
-                let y_source = (y as i32) + y_offset;
-
-                if x_source < 0 || y_source < 0 || x_source >= w as i32 || y_source >= h as i32 {
-                    return None;
-                }
-
-                Some((x_source as u32, y_source as u32))
-            })
+        DIRECTIONS.iter().filter_map(move |(x_offset, y_offset)| {
+            let x_source = (x as i32) + x_offset;
+            let y_source = (y as i32) + y_offset;
+
+            if x_source < 0 || y_source < 0 || x_source >= w as i32 || y_source >= h as i32 {
+                return None;
+            }
+
+            Some((x_source as u32, y_source as u32))
+        })
     };
 
     // Populate the set of initial positions to visit as well as positions that
@@ -72,20 +80,36 @@ pub(crate) fn alpha_bleed(image: &mut Image) {
         }
     }
 
+    let to_blend_space = |value: u8| {
+        if linear_blending {
+            srgb_to_linear(value) * 255.0
+        } else {
+            value as f32
+        }
+    };
+
+    let from_blend_space = |value: f32| {
+        if linear_blending {
+            linear_to_srgb(value / 255.0)
+        } else {
+            value as u8
+        }
+    };
+
     while let Some((x, y)) = to_visit.pop_front() {
         // Compute the average color from all surrounding pixels that are
         // eligible to be sampled from.
-        let mut new_color = (0, 0, 0);
-        let mut contributing = 0;
+        let mut new_color = (0.0, 0.0, 0.0);
+        let mut contributing = 0.0;
 
         for (x_source, y_source) in adjacent_positions(x, y) {
             if can_be_sampled.get(x_source, y_source) {
                 let source = image.get_pixel((x_source, y_source));
 
-                contributing += 1;
-                new_color.0 += source.r as u16;
-                new_color.1 += source.g as u16;
-                new_color.2 += source.b as u16;
+                contributing += 1.0;
+                new_color.0 += to_blend_space(source.r);
+                new_color.1 += to_blend_space(source.g);
+                new_color.2 += to_blend_space(source.b);
             } else if !visited.get(x_source, y_source) {
                 visited.set(x_source, y_source);
                 to_visit.push_back((x_source, y_source));
@@ -93,9 +117,9 @@ pub(crate) fn alpha_bleed(image: &mut Image) {
         }
 
         let new_color = Pixel::new(
-            (new_color.0 / contributing) as u8,
-            (new_color.1 / contributing) as u8,
-            (new_color.2 / contributing) as u8,
+            from_blend_space(new_color.0 / contributing),
+            from_blend_space(new_color.1 / contributing),
+            from_blend_space(new_color.2 / contributing),
             0,
         );
 
@@ -143,3 +167,30 @@ impl Mask2 {
         self.data[index as usize] = true;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A transparent pixel between a black and a white pixel.
+    fn high_contrast_edge() -> Image {
+        let mut image = Image::new_empty_rgba8((3, 1));
+        image.set_pixel((0, 0), Pixel::new(0, 0, 0, 255));
+        image.set_pixel((2, 0), Pixel::new(255, 255, 255, 255));
+        image
+    }
+
+    #[test]
+    fn linear_blending_differs_from_srgb() {
+        let mut naive = high_contrast_edge();
+        alpha_bleed(&mut naive, false);
+
+        let mut linear = high_contrast_edge();
+        alpha_bleed(&mut linear, true);
+
+        // Averaging sRGB values gives a gray that's darker than the average of
+        // the light that black and white give off.
+        assert_eq!(naive.get_pixel((1, 0)), Pixel::new(127, 127, 127, 0));
+        assert_eq!(linear.get_pixel((1, 0)), Pixel::new(188, 188, 188, 0));
+    }
+}
@@ -6,6 +6,28 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
+/// How asset names are written. Names are used as the keys of the manifest, so
+/// a project's manifest records which style it was written with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssetNameStyle {
+    /// The asset's path relative to the project, like `icons/Close.png`.
+    #[default]
+    Path,
+
+    /// The asset's path without its extension, like `icons/Close`.
+    Stem,
+
+    /// The asset's path in lowercase, like `icons/close.png`.
+    LowercasePath,
+}
+
+impl AssetNameStyle {
+    pub fn is_path(&self) -> bool {
+        *self == AssetNameStyle::Path
+    }
+}
+
 /// Represents a disambiguated and cleaned up path to an asset from a Tarmac
 /// project.
 ///
@@ -17,12 +39,19 @@ use serde::{Deserialize, Serialize};
 pub struct AssetName(Arc<str>);
 
 impl AssetName {
-    pub fn from_paths(root_path: &Path, asset_path: &Path) -> Self {
-        let relative = asset_path
-            .strip_prefix(root_path)
-            .expect("AssetName::from_paths expects asset_path to have root_path as a prefix.");
+    /// Creates an asset name from the path to an asset relative to the root of
+    /// the project, written in the given style.
+    ///
+    /// Assets that live outside of the project, like ones found by an absolute
+    /// glob, are named by their full path instead.
+    pub fn from_paths(root_path: &Path, asset_path: &Path, style: AssetNameStyle) -> Self {
+        let relative = asset_path.strip_prefix(root_path).unwrap_or(asset_path);
 
-        let displayed = format!("{}", relative.display());
+        let displayed = match style {
+            AssetNameStyle::Path => format!("{}", relative.display()),
+            AssetNameStyle::Stem => format!("{}", relative.with_extension("").display()),
+            AssetNameStyle::LowercasePath => format!("{}", relative.display()).to_lowercase(),
+        };
 
         // In order to make relative paths behave cross-platform, fix the path
         // separator to always be / on platforms where it isn't the main separator.
@@ -52,3 +81,20 @@ impl fmt::Display for AssetName {
         write!(formatter, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn name_styles() {
+        let root = Path::new("/project");
+        let path = Path::new("/project/icons/Close.png");
+
+        let name = |style| AssetName::from_paths(root, path, style).to_string();
+
+        assert_eq!(name(AssetNameStyle::Path), "icons/Close.png");
+        assert_eq!(name(AssetNameStyle::Stem), "icons/Close");
+        assert_eq!(name(AssetNameStyle::LowercasePath), "icons/close.png");
+    }
+}
@@ -1,8 +1,67 @@
-//! Implementation of automatically fetching authentication cookie from a Roblox
-//! Studio installation.
+//! Implementation of automatically fetching authentication cookie from the
+//! environment or a Roblox Studio installation.
 
-#[cfg(windows)]
+use std::env;
+
+use fs_err as fs;
+
+/// An environment variable that holds the cookie itself.
+const COOKIE_VAR: &str = "TARMAC_AUTH_COOKIE";
+
+/// An environment variable that holds the path to a file containing the
+/// cookie, which keeps the cookie out of the environment of other processes.
+const COOKIE_FILE_VAR: &str = "TARMAC_AUTH_COOKIE_FILE";
+
+/// Finds a cookie that wasn't passed explicitly. The environment is checked
+/// first so that it works on any platform, like Linux CI machines without
+/// Roblox Studio, and can override the cookie from a Studio install.
 pub fn get_auth_cookie() -> Option<String> {
+    get_env_cookie().or_else(get_studio_cookie)
+}
+
+fn get_env_cookie() -> Option<String> {
+    if let Some(cookie) = env::var(COOKIE_VAR).ok().and_then(non_empty) {
+        log::debug!("Using cookie from {}", COOKIE_VAR);
+        return Some(cookie);
+    }
+
+    let path = env::var_os(COOKIE_FILE_VAR)?;
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => {
+            let cookie = non_empty(contents);
+
+            if cookie.is_some() {
+                log::debug!("Using cookie from file in {}", COOKIE_FILE_VAR);
+            } else {
+                log::warn!("The cookie file given by {} is empty", COOKIE_FILE_VAR);
+            }
+
+            cookie
+        }
+        Err(err) => {
+            log::warn!(
+                "Couldn't read cookie file given by {}: {}",
+                COOKIE_FILE_VAR,
+                err
+            );
+            None
+        }
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let trimmed = value.trim();
+
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_owned())
+    }
+}
+
+#[cfg(windows)]
+fn get_studio_cookie() -> Option<String> {
     use winreg::{enums::HKEY_CURRENT_USER, RegKey};
 
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
@@ -40,6 +99,6 @@ pub fn get_auth_cookie() -> Option<String> {
 }
 
 #[cfg(not(windows))]
-pub fn get_auth_cookie() -> Option<String> {
+fn get_studio_cookie() -> Option<String> {
     None
 }
@@ -0,0 +1,63 @@
+//! Lets a sync be interrupted with Ctrl-C without losing its progress.
+//!
+//! While a sync is uploading, the first Ctrl-C only asks for the sync to stop.
+//! Tarmac finishes the upload that's in flight, saves the manifest, and exits.
+//! A second Ctrl-C, or any Ctrl-C outside of uploading, exits right away like
+//! Ctrl-C normally would.
+
+use std::{
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Once,
+    },
+};
+
+static CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
+static UPLOADING: AtomicBool = AtomicBool::new(false);
+static INSTALL_HANDLER: Once = Once::new();
+
+/// The exit code of a process that was stopped by Ctrl-C.
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Tells whether Ctrl-C has been pressed while uploading.
+pub fn is_requested() -> bool {
+    CANCEL_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Makes the first Ctrl-C ask the sync to stop instead of exiting, until the
+/// returned guard is dropped.
+pub fn while_uploading() -> UploadingGuard {
+    INSTALL_HANDLER.call_once(|| {
+        if let Err(err) = ctrlc::set_handler(handle_ctrl_c) {
+            log::warn!(
+                "Could not handle Ctrl-C, so it will stop the sync without saving progress: {}",
+                err
+            );
+        }
+    });
+
+    UPLOADING.store(true, Ordering::SeqCst);
+    UploadingGuard { _private: () }
+}
+
+/// Returned by `while_uploading`. Once this is dropped, Ctrl-C exits right
+/// away again.
+pub struct UploadingGuard {
+    _private: (),
+}
+
+impl Drop for UploadingGuard {
+    fn drop(&mut self) {
+        UPLOADING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Marks cancellation as requested, or exits immediately if it already was or
+/// if nothing is being uploaded. `ctrlc` runs this on its own thread, so it can
+/// log and exit like any other code.
+fn handle_ctrl_c() {
+    if !UPLOADING.load(Ordering::SeqCst) || CANCEL_REQUESTED.swap(true, Ordering::SeqCst) {
+        process::exit(INTERRUPTED_EXIT_CODE);
+    }
+}
@@ -3,27 +3,120 @@
 //! Tarmac uses a small Lua AST to build up generated code.
 
 use std::{
-    collections::BTreeMap,
-    io::{self, Write},
-    path::{self, Path},
+    collections::{BTreeMap, HashSet},
+    io::{self, Read, Write},
+    path::{self, Path, PathBuf},
 };
 
 use fs_err::File;
+use walkdir::WalkDir;
 
 use crate::{
-    data::ImageSlice,
-    data::SyncInput,
+    asset_name::AssetName,
+    data::{CodegenSliceLayout, Config, ImageSlice, SyncInput},
     lua_ast::{Block, Expression, Function, IfBlock, Statement, Table},
 };
 
 const CODEGEN_HEADER: &str =
     "-- This file was @generated by Tarmac. It is not intended for manual editing.";
 
-pub fn perform_codegen(output_path: Option<&Path>, inputs: &[&SyncInput]) -> io::Result<()> {
+/// Generates code for a group of inputs that share the same `codegen_path`.
+///
+/// `root_config` is the config that the sync was started from, which holds
+/// project-wide codegen settings.
+pub fn perform_codegen(
+    root_config: &Config,
+    output_path: Option<&Path>,
+    inputs: &[&SyncInput],
+) -> io::Result<()> {
     if let Some(path) = output_path {
-        codegen_grouped(path, inputs)
+        if root_config.codegen_rojo_tree {
+            codegen_rojo_tree(root_config, path, inputs)
+        } else {
+            codegen_grouped(root_config, path, inputs)
+        }
+    } else {
+        codegen_individual(root_config, inputs)
+    }
+}
+
+/// Finds the inputs that have been uploaded, but that won't be in any generated
+/// code. These are inputs from groups without `codegen`, and inputs whose
+/// names in a grouped module were taken by another input.
+pub fn inputs_missing_from_codegen<'a>(inputs: &[&'a SyncInput]) -> Vec<&'a SyncInput> {
+    let mut groups: BTreeMap<&Path, Vec<&'a SyncInput>> = BTreeMap::new();
+    for &input in inputs {
+        if let Some(path) = &input.config.codegen_path {
+            groups.entry(path).or_default().push(input);
+        }
+    }
+
+    // Inputs whose paths can't be turned into names are reported as errors
+    // when generating code, so they don't need to be reported again here.
+    let mut in_grouped_code = HashSet::new();
+    for group in groups.values() {
+        if let Ok(tree) = build_grouped_tree(group) {
+            collect_tree_inputs(&tree, &mut in_grouped_code);
+        }
+    }
+
+    inputs
+        .iter()
+        .copied()
+        .filter(|input| input.id.is_some())
+        .filter(|input| {
+            !input.config.codegen
+                || (input.config.codegen_path.is_some() && !in_grouped_code.contains(&input.name))
+        })
+        .collect()
+}
+
+fn collect_tree_inputs<'a>(
+    folder: &BTreeMap<String, GroupedItem<'a>>,
+    names: &mut HashSet<&'a AssetName>,
+) {
+    for item in folder.values() {
+        match item {
+            GroupedItem::Folder { children_by_name } => {
+                collect_tree_inputs(children_by_name, names)
+            }
+            GroupedItem::InputGroup {
+                inputs_by_dpi_scale,
+            } => names.extend(inputs_by_dpi_scale.values().map(|input| &input.name)),
+        }
+    }
+}
+
+/// Writes a generated Lua file, preceded by a header comment unless the project
+/// has turned it off.
+fn write_lua_file(root_config: &Config, path: &Path, ast: &Statement) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(render_lua_file(root_config.codegen_header, ast).as_bytes())
+}
+
+fn render_lua_file(header: bool, ast: &Statement) -> String {
+    if header {
+        format!("{}\n{}", CODEGEN_HEADER, ast)
     } else {
-        codegen_individual(inputs)
+        ast.to_string()
+    }
+}
+
+/// Project-wide settings for the code generated for packed images.
+#[derive(Debug, Clone, Copy, Default)]
+struct SliceOptions {
+    layout: CodegenSliceLayout,
+
+    /// Whether to include the tags of each image's input.
+    tags: bool,
+}
+
+impl SliceOptions {
+    fn new(root_config: &Config) -> Self {
+        Self {
+            layout: root_config.codegen_slice_layout,
+            tags: root_config.codegen_tags,
+        }
     }
 }
 
@@ -42,8 +135,166 @@ enum GroupedItem<'a> {
 ///
 /// We'll build up a Lua file containing nested tables that match the structure
 /// of the input's path with its base path stripped away.
-fn codegen_grouped(output_path: &Path, inputs: &[&SyncInput]) -> io::Result<()> {
-    let mut root_folder: BTreeMap<String, GroupedItem<'_>> = BTreeMap::new();
+fn codegen_grouped(
+    root_config: &Config,
+    output_path: &Path,
+    inputs: &[&SyncInput],
+) -> io::Result<()> {
+    let ast = build_grouped_ast(inputs, SliceOptions::new(root_config))?;
+
+    write_lua_file(root_config, output_path, &ast)
+}
+
+/// Perform codegen for a group of inputs who have `codegen_path` defined, but
+/// as a folder of ModuleScripts that Rojo can sync instead of a single file.
+///
+/// The folder is `output_path` without its extension, so that Rojo gives the
+/// folder's `init.lua` the same name and place that the single file would have
+/// had. Modules that Tarmac generated in earlier syncs but not this one are
+/// deleted, while files that don't start with Tarmac's header are left alone.
+fn codegen_rojo_tree(
+    root_config: &Config,
+    output_path: &Path,
+    inputs: &[&SyncInput],
+) -> io::Result<()> {
+    let root_folder = build_grouped_tree(inputs)?;
+    let files = build_rojo_tree(&root_folder, SliceOptions::new(root_config))?;
+
+    let tree_path = output_path.with_extension("");
+    let mut written = HashSet::new();
+
+    for (relative_path, ast) in &files {
+        let path = tree_path.join(relative_path);
+
+        if let Some(parent) = path.parent() {
+            fs_err::create_dir_all(parent)?;
+        }
+
+        write_lua_file(root_config, &path, ast)?;
+        written.insert(path);
+    }
+
+    // Folders are only removed when removing stale modules left them empty,
+    // so that empty folders made by hand stay where they are.
+    let mut emptied = HashSet::new();
+
+    for entry in WalkDir::new(&tree_path).contents_first(true) {
+        let entry = entry.map_err(io::Error::from)?;
+        let path = entry.path();
+
+        if entry.file_type().is_dir() {
+            if !emptied.contains(path) || fs_err::read_dir(path)?.next().is_some() {
+                continue;
+            }
+
+            fs_err::remove_dir(path)?;
+        } else {
+            if path.extension() != Some("lua".as_ref())
+                || written.contains(path)
+                || !is_generated_file(path)?
+            {
+                continue;
+            }
+
+            log::info!("Removing stale generated module {}", path.display());
+            fs_err::remove_file(path)?;
+        }
+
+        if let Some(parent) = path.parent() {
+            emptied.insert(parent.to_owned());
+        }
+    }
+
+    Ok(())
+}
+
+/// Tells whether the file at the given path starts with Tarmac's header
+/// comment, which is how Tarmac knows that it's safe to delete.
+fn is_generated_file(path: &Path) -> io::Result<bool> {
+    let mut header = Vec::new();
+    File::open(path)?
+        .take(CODEGEN_HEADER.len() as u64)
+        .read_to_end(&mut header)?;
+
+    Ok(header == CODEGEN_HEADER.as_bytes())
+}
+
+/// Lays out the tree from `build_grouped_tree` as Lua files, with paths
+/// relative to the tree's root folder. Each image becomes a `<name>.lua` module
+/// returning its value, and each folder gets an `init.lua` that returns a table
+/// requiring each of its children.
+fn build_rojo_tree(
+    folder: &BTreeMap<String, GroupedItem<'_>>,
+    options: SliceOptions,
+) -> io::Result<Vec<(PathBuf, Statement)>> {
+    let mut files = Vec::new();
+    let mut entries = Vec::new();
+
+    for (name, child) in folder {
+        // Rojo would treat a module named `init` as its folder's module.
+        if name == "init" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "An image or folder named 'init' can't be generated as part of a Rojo tree",
+            ));
+        }
+
+        match child {
+            GroupedItem::Folder { children_by_name } => {
+                let child_files = build_rojo_tree(children_by_name, options)?;
+
+                files.extend(
+                    child_files
+                        .into_iter()
+                        .map(|(path, ast)| (Path::new(name).join(path), ast)),
+                );
+            }
+            GroupedItem::InputGroup { .. } => match build_item(child, options) {
+                Some(value) => {
+                    files.push((
+                        PathBuf::from(format!("{}.lua", name)),
+                        Statement::Return(value),
+                    ));
+                }
+                None => continue,
+            },
+        }
+
+        let require = Expression::require(Expression::index_path("script", &[name]));
+        entries.push((name.into(), require));
+    }
+
+    files.push((
+        PathBuf::from("init.lua"),
+        Statement::Return(Expression::table(entries)),
+    ));
+
+    Ok(files)
+}
+
+/// Builds the module for `codegen_grouped`.
+fn build_grouped_ast(inputs: &[&SyncInput], options: SliceOptions) -> io::Result<Statement> {
+    let root_folder = build_grouped_tree(inputs)?;
+
+    let root_item = build_item(
+        &GroupedItem::Folder {
+            children_by_name: root_folder,
+        },
+        options,
+    )
+    .unwrap();
+
+    Ok(Statement::Return(root_item))
+}
+
+/// Groups inputs into a tree that matches the structure of their paths with
+/// their base paths stripped away. Everything is kept in sorted maps so that
+/// the same inputs always produce the same code, no matter what order they're
+/// given in. That keeps committed generated files from churning.
+fn build_grouped_tree<'a>(
+    inputs: &[&'a SyncInput],
+) -> io::Result<BTreeMap<String, GroupedItem<'a>>> {
+    let mut root_folder: BTreeMap<String, GroupedItem<'a>> = BTreeMap::new();
 
     // First, collect all of the inputs and group them together into a tree
     // according to their relative paths.
@@ -61,9 +312,7 @@ fn codegen_grouped(output_path: &Path, inputs: &[&SyncInput]) -> io::Result<()>
 
         // If we can't construct a relative path, there isn't a sensible name
         // that we can use to refer to this input.
-        let relative_path = path_without_extension
-            .strip_prefix(&input.config.codegen_base_path)
-            .expect("Input base path was not a base path for input");
+        let relative_path = strip_codegen_base_path(input, &path_without_extension)?;
 
         // Collapse `..` path segments so that we can map this path onto our
         // tree of inputs.
@@ -102,7 +351,22 @@ fn codegen_grouped(output_path: &Path, inputs: &[&SyncInput]) -> io::Result<()>
                     inputs_by_dpi_scale,
                 } = input_group
                 {
-                    inputs_by_dpi_scale.insert(input.dpi_scale, input);
+                    // Inputs that only differ by extension, like `foo.png` and
+                    // `foo.jpg`, end up with the same name. Pick the same one
+                    // every time regardless of input order.
+                    let existing = inputs_by_dpi_scale.entry(input.dpi_scale).or_insert(input);
+
+                    if existing.name != input.name {
+                        log::warn!(
+                            "{} and {} have the same name in generated code",
+                            existing.name,
+                            input.name
+                        );
+
+                        if input.name < existing.name {
+                            *existing = input;
+                        }
+                    }
                 } else {
                     unreachable!();
                 }
@@ -123,77 +387,311 @@ fn codegen_grouped(output_path: &Path, inputs: &[&SyncInput]) -> io::Result<()>
         }
     }
 
-    fn build_item(item: &GroupedItem<'_>) -> Option<Expression> {
-        match item {
-            GroupedItem::Folder { children_by_name } => {
-                let entries = children_by_name
-                    .iter()
-                    .filter_map(|(name, child)| build_item(child).map(|item| (name.into(), item)))
-                    .collect();
+    Ok(root_folder)
+}
+
+/// Turns an item from `build_grouped_tree` into the value that generated code
+/// gives it. Returns `None` for images that haven't been uploaded.
+fn build_item(item: &GroupedItem<'_>, options: SliceOptions) -> Option<Expression> {
+    match item {
+        GroupedItem::Folder { children_by_name } => {
+            let entries = children_by_name
+                .iter()
+                .filter_map(|(name, child)| {
+                    build_item(child, options).map(|item| (name.into(), item))
+                })
+                .collect();
+
+            Some(Expression::table(entries))
+        }
+        GroupedItem::InputGroup {
+            inputs_by_dpi_scale,
+        } => {
+            if inputs_by_dpi_scale.len() == 1 {
+                // If there is exactly one input in this group, we can
+                // generate code knowing that there are no high DPI variants
+                // to choose from.
+
+                let input = inputs_by_dpi_scale.values().next().unwrap();
 
-                Some(Expression::table(entries))
+                input.id.and_then(|id| codegen_input(input, id, options))
+            } else {
+                // In this case, we have the same asset in multiple
+                // different DPI scales. We can generate code to pick
+                // between them at runtime.
+                codegen_with_high_dpi_options(inputs_by_dpi_scale, options)
             }
-            GroupedItem::InputGroup {
-                inputs_by_dpi_scale,
-            } => {
-                if inputs_by_dpi_scale.len() == 1 {
-                    // If there is exactly one input in this group, we can
-                    // generate code knowing that there are no high DPI variants
-                    // to choose from.
-
-                    let input = inputs_by_dpi_scale.values().next().unwrap();
-
-                    match (input.id, input.slice) {
-                        (Some(id), Some(slice)) => Some(codegen_url_and_slice(id, slice)),
-                        (Some(id), None) => Some(codegen_just_asset_url(id)),
-                        _ => None,
-                    }
-                } else {
-                    // In this case, we have the same asset in multiple
-                    // different DPI scales. We can generate code to pick
-                    // between them at runtime.
-                    Some(codegen_with_high_dpi_options(inputs_by_dpi_scale))
-                }
+        }
+    }
+}
+
+/// Generate a module that requires each of the given grouped modules, keyed by
+/// their file names.
+///
+/// Module paths are turned into instance paths relative to the index module,
+/// starting from `require_prefix`.
+pub fn codegen_index(
+    root_config: &Config,
+    output_path: &Path,
+    module_paths: &[&Path],
+) -> io::Result<()> {
+    let require_prefix = root_config.codegen_require_prefix.as_str();
+    let index_folder = output_path.parent().unwrap();
+    let mut modules_by_name = BTreeMap::new();
+
+    for &module_path in module_paths {
+        let name = match module_path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) => name,
+            None => {
+                log::warn!(
+                    "{} has no usable file name, skipping it in the codegen index",
+                    module_path.display()
+                );
+                continue;
             }
+        };
+
+        let segments = match relative_instance_path(index_folder, module_path) {
+            Some(segments) => segments,
+            None => {
+                log::warn!(
+                    "Cannot require {} from {}, skipping it in the codegen index",
+                    module_path.display(),
+                    output_path.display()
+                );
+                continue;
+            }
+        };
+
+        let require = Expression::require(Expression::index_path(require_prefix, &segments));
+
+        if modules_by_name.insert(name.to_owned(), require).is_some() {
+            log::warn!(
+                "More than one grouped codegen module is named '{}', only {} will be in the codegen index",
+                name,
+                module_path.display()
+            );
         }
     }
 
-    let root_item = build_item(&GroupedItem::Folder {
-        children_by_name: root_folder,
-    })
-    .unwrap();
-    let ast = Statement::Return(root_item);
+    let entries = modules_by_name
+        .into_iter()
+        .map(|(name, require)| (name.into(), require))
+        .collect();
+    let ast = Statement::Return(Expression::table(entries));
 
-    let mut file = File::create(output_path)?;
-    writeln!(file, "{}", CODEGEN_HEADER)?;
-    write!(file, "{}", ast)?;
+    write_lua_file(root_config, output_path, &ast)
+}
 
-    Ok(())
+/// Computes the names of the instances that need to be traversed to get from
+/// `from_folder` to the module at `to_path`, where `Parent` goes up one level.
+fn relative_instance_path(from_folder: &Path, to_path: &Path) -> Option<Vec<String>> {
+    let from: Vec<_> = from_folder.components().collect();
+    let to_path = to_path.with_extension("");
+    let to: Vec<_> = to_path.components().collect();
+
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    // Paths that share nothing, like ones on different drives on Windows,
+    // can't be related to eachother.
+    if common == 0 {
+        return None;
+    }
+
+    let mut segments: Vec<String> = from[common..].iter().map(|_| "Parent".to_owned()).collect();
+
+    for component in &to[common..] {
+        segments.push(component.as_os_str().to_str()?.to_owned());
+    }
+
+    Some(segments)
 }
 
 /// Perform codegen for a group of inputs that don't have `codegen_path`
 /// defined, and so generate individual files.
-fn codegen_individual(inputs: &[&SyncInput]) -> io::Result<()> {
+fn codegen_individual(root_config: &Config, inputs: &[&SyncInput]) -> io::Result<()> {
     for input in inputs {
-        let expression = match (input.id, input.slice) {
-            (Some(id), Some(slice)) => codegen_url_and_slice(id, slice),
-            (Some(id), None) => codegen_just_asset_url(id),
-            _ => continue,
+        let expression = match input
+            .id
+            .and_then(|id| codegen_input(input, id, SliceOptions::new(root_config)))
+        {
+            Some(expression) => expression,
+            None => continue,
         };
 
         let ast = Statement::Return(expression);
 
-        let path = input.path.with_extension("lua");
+        let path = individual_codegen_path(input)?;
+        if let Some(parent) = path.parent() {
+            fs_err::create_dir_all(parent)?;
+        }
 
-        let mut file = File::create(path)?;
-        writeln!(file, "{}", CODEGEN_HEADER)?;
-        write!(file, "{}", ast)?;
+        write_lua_file(root_config, &path, &ast)?;
     }
 
     Ok(())
 }
 
-fn codegen_url_and_slice(id: u64, slice: ImageSlice) -> Expression {
+/// Generated files live next to their images unless the input asks for them
+/// to be mirrored into another folder.
+fn individual_codegen_path(input: &SyncInput) -> io::Result<PathBuf> {
+    match &input.config.codegen_output_dir {
+        Some(output_dir) => {
+            let relative_path = strip_codegen_base_path(input, &input.path)?;
+            Ok(output_dir.join(relative_path).with_extension("lua"))
+        }
+        None => Ok(input.path.with_extension("lua")),
+    }
+}
+
+/// Finds where the given path, which came from `input`, is relative to the
+/// input's `codegen_base_path`.
+fn strip_codegen_base_path<'a>(input: &SyncInput, path: &'a Path) -> io::Result<&'a Path> {
+    path.strip_prefix(&input.config.codegen_base_path)
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Input {} is not inside of its codegen-base-path, {}",
+                    input.path.display(),
+                    input.config.codegen_base_path.display()
+                ),
+            )
+        })
+}
+
+/// Generates the value for an input that has been uploaded to the given asset
+/// ID, depending on whether it was packed, split into tiles, or neither.
+/// Returns `None` for packed images that can't be shown.
+fn codegen_input(input: &SyncInput, id: u64, options: SliceOptions) -> Option<Expression> {
+    if !input.tiles.is_empty() {
+        return Some(codegen_tiles(input, options));
+    }
+
+    if let Some(warning) = empty_slice_warning(input) {
+        log::warn!("{}", warning);
+        return None;
+    }
+
+    let expression = match input.slice {
+        Some(slice) => codegen_slice(input, id, slice, options),
+        None => codegen_just_asset_url(id),
+    };
+
+    Some(expression)
+}
+
+/// Describes the problem with a packed image whose slice has no pixels in it,
+/// if it has one. Code for these would set `ImageRectSize` to zero and show
+/// nothing, which hides the real problem, so they're left out instead.
+fn empty_slice_warning(input: &SyncInput) -> Option<String> {
+    let (width, height) = input.slice?.size();
+
+    if width != 0 && height != 0 {
+        return None;
+    }
+
+    Some(format!(
+        "{} was packed into an empty {}x{} slice, so it was left out of generated code. \
+         Check that the image isn't empty or trimmed away entirely.",
+        input.name, width, height
+    ))
+}
+
+/// Generates the table for an image that was split into tiles, which lists the
+/// image's full size and where each tile goes in it, like
+/// `{ Size = Vector2.new(width, height), Tiles = { { Image = "rbxassetid://...",
+/// Position = Vector2.new(x, y), Size = Vector2.new(width, height) }, ... } }`.
+/// Each tile can be shown with its own `ImageLabel`.
+fn codegen_tiles(input: &SyncInput, options: SliceOptions) -> Expression {
+    fn vector2((x, y): (u32, u32)) -> Expression {
+        Expression::Raw(format!("Vector2.new({}, {})", x, y))
+    }
+
+    fn array((x, y): (u32, u32)) -> Expression {
+        Expression::array(vec![
+            Expression::Raw(x.to_string()),
+            Expression::Raw(y.to_string()),
+        ])
+    }
+
+    let size = input.tiles.iter().fold((0, 0), |size, tile| {
+        let (x, y) = tile.slice.max();
+        (size.0.max(x), size.1.max(y))
+    });
+
+    let image_label = options.layout == CodegenSliceLayout::ImageLabel;
+
+    let tiles = input
+        .tiles
+        .iter()
+        .map(|tile| {
+            let url = format!("rbxassetid://{}", tile.id);
+            let mut table = Table::new();
+
+            if image_label {
+                table.add_entry("Image", url);
+                table.add_entry("Position", vector2(tile.slice.min()));
+                table.add_entry("Size", vector2(tile.slice.size()));
+            } else {
+                table.add_entry("id", url);
+                table.add_entry("position", array(tile.slice.min()));
+                table.add_entry("size", array(tile.slice.size()));
+            }
+
+            Expression::Table(table)
+        })
+        .collect();
+
+    let mut table = Table::new();
+
+    if image_label {
+        table.add_entry("Size", vector2(size));
+        table.add_entry("Tiles", Expression::array(tiles));
+    } else {
+        table.add_entry("size", array(size));
+        table.add_entry("tiles", Expression::array(tiles));
+    }
+
+    if input.dpi_scale != 1 {
+        let key = if image_label { "DpiScale" } else { "dpiScale" };
+        table.add_entry(key, Expression::Raw(input.dpi_scale.to_string()));
+    }
+
+    if options.tags && !input.config.tags.is_empty() {
+        let key = if image_label { "Tags" } else { "tags" };
+        let tags = input.config.tags.iter().map(Expression::from).collect();
+        table.add_entry(key, Expression::array(tags));
+    }
+
+    Expression::Table(table)
+}
+
+/// Generates the table for a packed image in the layout the project asked for.
+fn codegen_slice(
+    input: &SyncInput,
+    id: u64,
+    slice: ImageSlice,
+    options: SliceOptions,
+) -> Expression {
+    let (mut table, tags_key) = match options.layout {
+        CodegenSliceLayout::ImageLabel => (codegen_url_and_slice(input, id, slice), "Tags"),
+        CodegenSliceLayout::Rect => (codegen_url_and_rect(input, id, slice), "tags"),
+    };
+
+    if options.tags && !input.config.tags.is_empty() {
+        let tags = input.config.tags.iter().map(Expression::from).collect();
+        table.add_entry(tags_key, Expression::array(tags));
+    }
+
+    Expression::Table(table)
+}
+
+/// Generates the table for a packed image. Every size and position in it is in
+/// the pixels of the spritesheet, which for high DPI images is larger than the
+/// size the image should be displayed at. Those images get a `DpiScale` entry
+/// to divide by; when it's missing, the scale is 1.
+fn codegen_url_and_slice(input: &SyncInput, id: u64, slice: ImageSlice) -> Table {
     let offset = slice.min();
     let size = slice.size();
 
@@ -209,43 +707,110 @@ fn codegen_url_and_slice(id: u64, slice: ImageSlice) -> Expression {
         Expression::Raw(format!("Vector2.new({}, {})", size.0, size.1)),
     );
 
-    Expression::Table(table)
+    if input.dpi_scale != 1 {
+        table.add_entry("DpiScale", Expression::Raw(input.dpi_scale.to_string()));
+    }
+
+    // Trimmed images need to know where they were in the original image in
+    // order to be positioned correctly.
+    if let Some(trim_info) = input.trim_info {
+        let (width, height) = trim_info.original_size;
+        table.add_entry(
+            "OriginalSize",
+            Expression::Raw(format!("Vector2.new({}, {})", width, height)),
+        );
+
+        let (x, y) = trim_info.offset;
+        table.add_entry(
+            "TrimOffset",
+            Expression::Raw(format!("Vector2.new({}, {})", x, y)),
+        );
+    }
+
+    table
+}
+
+/// Generates the compact form of a packed image's table, which has the same
+/// information as `codegen_url_and_slice` with every position and size written
+/// as an array.
+fn codegen_url_and_rect(input: &SyncInput, id: u64, slice: ImageSlice) -> Table {
+    fn array(values: &[u32]) -> Expression {
+        Expression::array(
+            values
+                .iter()
+                .map(|value| Expression::Raw(value.to_string()))
+                .collect(),
+        )
+    }
+
+    let offset = slice.min();
+    let size = slice.size();
+
+    let mut table = Table::new();
+    table.add_entry("id", format!("rbxassetid://{}", id));
+    table.add_entry("rect", array(&[offset.0, offset.1, size.0, size.1]));
+
+    if input.dpi_scale != 1 {
+        table.add_entry("dpiScale", Expression::Raw(input.dpi_scale.to_string()));
+    }
+
+    if let Some(trim_info) = input.trim_info {
+        let (width, height) = trim_info.original_size;
+        table.add_entry("originalSize", array(&[width, height]));
+
+        let (x, y) = trim_info.offset;
+        table.add_entry("trimOffset", array(&[x, y]));
+    }
+
+    table
 }
 
 fn codegen_just_asset_url(id: u64) -> Expression {
     Expression::String(format!("rbxassetid://{}", id))
 }
 
-fn codegen_dpi_option(input: &SyncInput) -> (Expression, Block) {
+fn codegen_dpi_option(input: &SyncInput, options: SliceOptions) -> Option<(Expression, Block)> {
     let condition = Expression::Raw(format!("dpiScale >= {}", input.dpi_scale));
 
     // FIXME: We should probably pull data out of SyncInput at the start of
     // codegen so that we can handle invariants like this.
     let id = input.id.unwrap();
 
-    let value = match input.slice {
-        Some(slice) => codegen_url_and_slice(id, slice),
-        None => codegen_just_asset_url(id),
-    };
+    let value = codegen_input(input, id, options)?;
 
     let body = Statement::Return(value);
 
-    (condition, body.into())
+    Some((condition, body.into()))
 }
 
-fn codegen_with_high_dpi_options(inputs: &BTreeMap<u32, &SyncInput>) -> Expression {
+/// Generates a function that picks between DPI variants at runtime. Variants
+/// that can't be shown are left out, and returns `None` if none of them can.
+fn codegen_with_high_dpi_options(
+    inputs: &BTreeMap<u32, &SyncInput>,
+    options: SliceOptions,
+) -> Option<Expression> {
     let args = "dpiScale".to_owned();
 
-    let mut options_high_to_low = inputs.values().rev().peekable();
+    let dpi_options: Vec<_> = inputs
+        .values()
+        .rev()
+        .filter_map(|input| codegen_dpi_option(input, options))
+        .collect();
+    let mut options_high_to_low = dpi_options.into_iter().peekable();
 
-    let highest_dpi_option = options_high_to_low.next().unwrap();
-    let (highest_cond, highest_body) = codegen_dpi_option(highest_dpi_option);
+    let (highest_cond, highest_body) = options_high_to_low.next()?;
 
-    let mut if_block = IfBlock::new(highest_cond, highest_body);
+    // With only one variant left, there's nothing to pick between.
+    if options_high_to_low.peek().is_none() {
+        return Some(Expression::Function(Function::new(
+            args,
+            highest_body.statements,
+        )));
+    }
 
-    while let Some(dpi_option) = options_high_to_low.next() {
-        let (cond, body) = codegen_dpi_option(dpi_option);
+    let mut if_block = IfBlock::new(highest_cond, highest_body);
 
+    while let Some((cond, body)) = options_high_to_low.next() {
         if options_high_to_low.peek().is_some() {
             if_block.else_if_blocks.push((cond, body));
         } else {
@@ -255,5 +820,385 @@ fn codegen_with_high_dpi_options(inputs: &BTreeMap<u32, &SyncInput>) -> Expressi
 
     let statements = vec![Statement::If(if_block)];
 
-    Expression::Function(Function::new(args, statements))
+    Some(Expression::Function(Function::new(args, statements)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{
+        data::{ImageTile, InputConfig},
+        glob::Glob,
+        image::ResizeFilter,
+        lua_ast::syntax,
+    };
+
+    fn input(path: &str, dpi_scale: u32, id: u64, slice: Option<ImageSlice>) -> SyncInput {
+        let path = PathBuf::from(path);
+        let path_without_dpi_scale = PathBuf::from(
+            path.to_str()
+                .unwrap()
+                .replace(&format!("@{}x", dpi_scale), ""),
+        );
+
+        SyncInput {
+            name: AssetName::new(path.to_str().unwrap()),
+            path,
+            path_without_dpi_scale,
+            dpi_scale,
+            config: InputConfig {
+                glob: Glob::new("assets/**/*.png").unwrap(),
+                codegen: true,
+                codegen_path: Some(PathBuf::from("assets.lua")),
+                codegen_base_path: PathBuf::from("assets"),
+                codegen_output_dir: None,
+                packable: slice.is_some(),
+                trim: false,
+                allow_tiling: false,
+                preview_asset_id: None,
+                preview_alt_text: None,
+                tags: Vec::new(),
+                atlas_group: None,
+                padding: None,
+                resize_filter: ResizeFilter::default(),
+            },
+            config_index: 0,
+            contents: Vec::new(),
+            hash: String::new(),
+            dimensions: None,
+            id: Some(id),
+            slice,
+            trim_info: None,
+            tiles: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn grouped_codegen_is_deterministic() {
+        let inputs = [
+            input("assets/b/logo.png", 1, 10, None),
+            input("assets/b/logo@2x.png", 2, 11, None),
+            input("assets/a.png", 1, 12, None),
+            input(
+                "assets/c/icon.png",
+                1,
+                13,
+                Some(ImageSlice::new((0, 0), (16, 16))),
+            ),
+            input("assets/b/banner.png", 1, 14, None),
+        ];
+
+        let forward: Vec<_> = inputs.iter().collect();
+        let backward: Vec<_> = inputs.iter().rev().collect();
+
+        let first = render_lua_file(
+            true,
+            &build_grouped_ast(&forward, SliceOptions::default()).unwrap(),
+        );
+        let second = render_lua_file(
+            true,
+            &build_grouped_ast(&forward, SliceOptions::default()).unwrap(),
+        );
+        let reversed = render_lua_file(
+            true,
+            &build_grouped_ast(&backward, SliceOptions::default()).unwrap(),
+        );
+
+        assert_eq!(first.as_bytes(), second.as_bytes());
+        assert_eq!(first.as_bytes(), reversed.as_bytes());
+        syntax::assert_valid_lua(&first);
+    }
+
+    #[test]
+    fn individual_codegen_paths() {
+        let mut colocated = input("assets/icons/close.png", 1, 1, None);
+        colocated.config.codegen_path = None;
+
+        assert_eq!(
+            individual_codegen_path(&colocated).unwrap(),
+            PathBuf::from("assets/icons/close.lua")
+        );
+
+        let mut mirrored = input("assets/icons/close.png", 1, 1, None);
+        mirrored.config.codegen_path = None;
+        mirrored.config.codegen_output_dir = Some(PathBuf::from("src/assets"));
+
+        assert_eq!(
+            individual_codegen_path(&mirrored).unwrap(),
+            PathBuf::from("src/assets/icons/close.lua")
+        );
+    }
+
+    #[test]
+    fn packed_high_dpi_images_include_dpi_scale() {
+        let slice = Some(ImageSlice::new((4, 8), (36, 40)));
+        let inputs = [
+            input("assets/icon.png", 1, 1, slice),
+            input("assets/icon@2x.png", 2, 2, slice),
+        ];
+        let inputs: Vec<_> = inputs.iter().collect();
+
+        let code = render_lua_file(
+            false,
+            &build_grouped_ast(&inputs, SliceOptions::default()).unwrap(),
+        );
+
+        assert_eq!(code.matches("DpiScale").count(), 1);
+        assert!(code.contains("DpiScale = 2"));
+        syntax::assert_valid_lua(&code);
+    }
+
+    #[test]
+    fn high_dpi_options_are_valid_lua() {
+        let inputs = [
+            input("assets/icon.png", 1, 1, None),
+            input("assets/icon@2x.png", 2, 2, None),
+            input("assets/icon@3x.png", 3, 3, None),
+        ];
+        let options: BTreeMap<_, _> = inputs
+            .iter()
+            .map(|input| (input.dpi_scale, input))
+            .collect();
+
+        let ast = Statement::Return(
+            codegen_with_high_dpi_options(&options, SliceOptions::default()).unwrap(),
+        );
+        let code = render_lua_file(true, &ast);
+
+        assert!(code.contains("elseif"));
+        assert!(code.contains("else\n"));
+        syntax::assert_valid_lua(&code);
+    }
+
+    #[test]
+    fn empty_slices_are_left_out() {
+        let empty = Some(ImageSlice::new((4, 8), (4, 8)));
+        let inputs = [
+            input("assets/empty.png", 1, 1, empty),
+            input(
+                "assets/icon.png",
+                1,
+                2,
+                Some(ImageSlice::new((0, 0), (16, 16))),
+            ),
+            input("assets/icon@2x.png", 2, 3, empty),
+        ];
+
+        let warning = empty_slice_warning(&inputs[0]).unwrap();
+        assert!(warning.contains("assets/empty.png"));
+        assert!(warning.contains("0x0"));
+        assert!(empty_slice_warning(&inputs[1]).is_none());
+
+        let inputs: Vec<_> = inputs.iter().collect();
+        let code = render_lua_file(
+            false,
+            &build_grouped_ast(&inputs, SliceOptions::default()).unwrap(),
+        );
+
+        // The 1x icon is still there, but nothing refers to the empty slices.
+        assert!(!code.contains("empty"));
+        assert!(!code.contains("ImageRectSize = Vector2.new(0, 0)"));
+        assert!(code.contains("rbxassetid://2"));
+        assert!(!code.contains("rbxassetid://3"));
+        syntax::assert_valid_lua(&code);
+    }
+
+    #[test]
+    fn grouped_codegen_picks_same_input_for_duplicate_names() {
+        let inputs = [
+            input("assets/foo.png", 1, 1, None),
+            input("assets/foo.jpg", 1, 2, None),
+        ];
+
+        let forward: Vec<_> = inputs.iter().collect();
+        let backward: Vec<_> = inputs.iter().rev().collect();
+
+        assert_eq!(
+            render_lua_file(
+                false,
+                &build_grouped_ast(&forward, SliceOptions::default()).unwrap()
+            ),
+            render_lua_file(
+                false,
+                &build_grouped_ast(&backward, SliceOptions::default()).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn rect_layout() {
+        let slice = Some(ImageSlice::new((4, 8), (36, 40)));
+        let inputs = [
+            input("assets/icon.png", 1, 1, slice),
+            input("assets/icon@2x.png", 2, 2, slice),
+        ];
+        let inputs: Vec<_> = inputs.iter().collect();
+
+        let code = render_lua_file(
+            false,
+            &build_grouped_ast(
+                &inputs,
+                SliceOptions {
+                    layout: CodegenSliceLayout::Rect,
+                    tags: false,
+                },
+            )
+            .unwrap(),
+        );
+
+        assert!(code.contains("id = \"rbxassetid://2\""));
+        assert!(code.contains("rect = {4, 8, 32, 32}"));
+        assert!(code.contains("dpiScale = 2"));
+        assert!(!code.contains("ImageRectOffset"));
+        syntax::assert_valid_lua(&code);
+    }
+
+    #[test]
+    fn uploaded_inputs_missing_from_codegen() {
+        let mut without_codegen = input("assets/hidden.png", 1, 1, None);
+        without_codegen.config.codegen = false;
+
+        let mut never_uploaded = input("assets/new.png", 1, 2, None);
+        never_uploaded.config.codegen = false;
+        never_uploaded.id = None;
+
+        let inputs = [
+            without_codegen,
+            never_uploaded,
+            input("assets/foo.png", 1, 3, None),
+            input("assets/foo.jpg", 1, 4, None),
+        ];
+        let inputs: Vec<_> = inputs.iter().collect();
+
+        let missing: Vec<_> = inputs_missing_from_codegen(&inputs)
+            .iter()
+            .map(|input| input.name.to_string())
+            .collect();
+
+        // foo.jpg and foo.png have the same name, and foo.jpg comes first.
+        assert_eq!(missing, ["assets/hidden.png", "assets/foo.png"]);
+    }
+
+    #[test]
+    fn rojo_tree_layout() {
+        let inputs = [
+            input("assets/logo.png", 1, 1, None),
+            input("assets/icons/close.png", 1, 2, None),
+            input("assets/icons/open file.png", 1, 3, None),
+        ];
+        let inputs: Vec<_> = inputs.iter().collect();
+
+        let tree = build_grouped_tree(&inputs).unwrap();
+        let files = build_rojo_tree(&tree, SliceOptions::default()).unwrap();
+
+        let rendered: BTreeMap<_, _> = files
+            .iter()
+            .map(|(path, ast)| (path.to_str().unwrap().replace('\\', "/"), ast.to_string()))
+            .collect();
+
+        let paths: Vec<_> = rendered.keys().map(String::as_str).collect();
+        assert_eq!(
+            paths,
+            [
+                "icons/close.lua",
+                "icons/init.lua",
+                "icons/open file.lua",
+                "init.lua",
+                "logo.lua",
+            ]
+        );
+
+        assert!(
+            rendered["icons/init.lua"].contains(r#"["open file"] = require(script["open file"])"#)
+        );
+        assert!(rendered["init.lua"].contains("icons = require(script.icons)"));
+        assert!(rendered["logo.lua"].contains("rbxassetid://1"));
+
+        for code in rendered.values() {
+            syntax::assert_valid_lua(code);
+        }
+    }
+
+    #[test]
+    fn rojo_tree_keeps_files_tarmac_did_not_generate() {
+        let project_dir =
+            std::env::temp_dir().join(format!("tarmac-rojo-tree-test-{}", std::process::id()));
+        let tree_path = project_dir.join("src/Assets");
+        fs_err::create_dir_all(tree_path.join("old")).unwrap();
+        fs_err::create_dir_all(tree_path.join("empty")).unwrap();
+
+        let stale = format!("{}\nreturn \"rbxassetid://1\"", CODEGEN_HEADER);
+        fs_err::write(tree_path.join("old/removed.lua"), &stale).unwrap();
+        fs_err::write(tree_path.join("Util.lua"), "return {}").unwrap();
+
+        let config = Config::from_slice(
+            b"name = \"test\"\ncodegen-rojo-tree = true\n",
+            project_dir.join("tarmac.toml"),
+        )
+        .unwrap();
+
+        let logo = input("assets/logo.png", 1, 2, None);
+        codegen_rojo_tree(&config, &project_dir.join("src/Assets.lua"), &[&logo]).unwrap();
+
+        assert!(tree_path.join("logo.lua").is_file());
+        assert!(tree_path.join("Util.lua").is_file());
+        assert!(tree_path.join("empty").is_dir());
+        assert!(!tree_path.join("old").exists());
+
+        fs_err::remove_dir_all(&project_dir).unwrap();
+    }
+
+    #[test]
+    fn tiled_image() {
+        let mut tiled = input("assets/background.png", 1, 1, None);
+        tiled.tiles = vec![
+            ImageTile {
+                id: 1,
+                slice: ImageSlice::new((0, 0), (1024, 600)),
+            },
+            ImageTile {
+                id: 2,
+                slice: ImageSlice::new((1024, 0), (1500, 600)),
+            },
+        ];
+        let inputs = [tiled];
+        let inputs: Vec<_> = inputs.iter().collect();
+
+        let code = render_lua_file(
+            false,
+            &build_grouped_ast(&inputs, SliceOptions::default()).unwrap(),
+        );
+
+        assert!(code.contains("Size = Vector2.new(1500, 600)"));
+        assert!(code.contains("Image = \"rbxassetid://2\""));
+        assert!(code.contains("Position = Vector2.new(1024, 0)"));
+        assert!(code.contains("Size = Vector2.new(476, 600)"));
+        syntax::assert_valid_lua(&code);
+    }
+
+    #[test]
+    fn tags_in_packed_images() {
+        let slice = Some(ImageSlice::new((0, 0), (16, 16)));
+        let mut tagged = input("assets/icon.png", 1, 1, slice);
+        tagged.config.tags = vec!["ui".to_owned(), "say \"hi\"".to_owned()];
+        let inputs = [tagged, input("assets/other.png", 1, 2, slice)];
+        let inputs: Vec<_> = inputs.iter().collect();
+
+        let options = SliceOptions {
+            tags: true,
+            ..SliceOptions::default()
+        };
+        let code = render_lua_file(false, &build_grouped_ast(&inputs, options).unwrap());
+
+        assert_eq!(code.matches("Tags = ").count(), 1);
+        assert!(code.contains(r#"Tags = {"ui", "say \"hi\""}"#));
+        syntax::assert_valid_lua(&code);
+
+        let without_tags = render_lua_file(
+            false,
+            &build_grouped_ast(&inputs, SliceOptions::default()).unwrap(),
+        );
+        assert!(!without_tags.contains("Tags"));
+    }
 }
@@ -0,0 +1,50 @@
+use std::env;
+
+use anyhow::bail;
+
+use crate::options::{AdoptOptions, GlobalOptions};
+use crate::roblox_web_api::{RobloxApiClient, RobloxOpenCloudCredentials};
+
+use super::sync::SyncSession;
+
+pub fn adopt(global: GlobalOptions, options: AdoptOptions) -> anyhow::Result<()> {
+    let credentials = RobloxOpenCloudCredentials::get_credentials(global.auth, global.api_key)?;
+    let mut api_client = RobloxApiClient::builder(credentials)
+        .proxy(global.proxy)
+        .root_certificate(global.root_certificate)
+        .csrf_retry(!global.no_csrf_retry)
+        .build()?;
+
+    let fuzzy_config_path = match options.project_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let mut session = SyncSession::new(&fuzzy_config_path)?;
+
+    session.discover_configs()?;
+    session.discover_inputs()?;
+
+    let name = match session.find_input_by_path(&options.path) {
+        Some(name) => name.clone(),
+        None => bail!(
+            "{} is not an input of this Tarmac project",
+            options.path.display()
+        ),
+    };
+
+    // Packed images live in spritesheets that Tarmac makes itself, so there's
+    // no existing asset that one of them could be.
+    if session.is_input_packable(&name) {
+        bail!(
+            "{} is packable, so it can't be adopted as its own asset",
+            options.path.display()
+        );
+    }
+
+    session.adopt_input(&name, options.asset_id, &mut api_client)?;
+
+    log::info!("{} now refers to asset ID {}", name, options.asset_id);
+
+    Ok(())
+}
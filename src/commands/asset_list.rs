@@ -1,10 +1,11 @@
 use std::collections::BTreeSet;
 use std::env;
-use std::io::{BufWriter, Write};
+use std::io::{self, BufWriter, Write};
 
 use fs_err as fs;
 
-use crate::data::Manifest;
+use crate::asset_name::AssetName;
+use crate::data::{AssetListFormat, Config, Manifest};
 use crate::options::{AssetListOptions, GlobalOptions};
 
 pub fn asset_list(_global: GlobalOptions, options: AssetListOptions) -> anyhow::Result<()> {
@@ -13,20 +14,91 @@ pub fn asset_list(_global: GlobalOptions, options: AssetListOptions) -> anyhow::
         None => env::current_dir()?,
     };
 
-    let manifest = Manifest::read_from_folder(&project_path)?;
+    // Projects can keep their manifest somewhere other than next to their
+    // config, so we need to check the config first.
+    let manifest = match Config::read_from_folder(&project_path) {
+        Ok(config) => Manifest::read_from_file(config.manifest_file_path())?,
+        Err(err) if err.is_not_found() => Manifest::read_from_folder(&project_path)?,
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut file = BufWriter::new(fs::File::create(&options.output)?);
 
-    let mut asset_list = BTreeSet::new();
-    for input_manifest in manifest.inputs.values() {
-        if let Some(id) = input_manifest.id {
-            asset_list.insert(id);
+    match options.format {
+        AssetListFormat::Plain => {
+            let mut asset_list = BTreeSet::new();
+            for input_manifest in manifest.inputs.values() {
+                if let Some(id) = input_manifest.id {
+                    asset_list.insert(id);
+                }
+            }
+
+            for id in asset_list {
+                writeln!(file, "{}", id)?;
+            }
         }
-    }
+        AssetListFormat::Csv => {
+            let assets = manifest.inputs.iter().flat_map(|(name, input_manifest)| {
+                input_manifest
+                    .id
+                    .into_iter()
+                    .chain(input_manifest.tiles.iter().map(|tile| tile.id))
+                    .map(move |id| (name, id))
+            });
 
-    let mut file = BufWriter::new(fs::File::create(&options.output)?);
-    for id in asset_list {
-        writeln!(file, "{}", id)?;
+            write_asset_csv(&mut file, assets)?;
+        }
     }
+
     file.flush()?;
 
     Ok(())
 }
+
+/// Writes a CSV list of assets with a header, one row per name and asset ID.
+pub(super) fn write_asset_csv<'a, W: Write>(
+    mut output: W,
+    assets: impl IntoIterator<Item = (&'a AssetName, u64)>,
+) -> io::Result<()> {
+    let assets: BTreeSet<_> = assets.into_iter().collect();
+
+    writeln!(output, "name,url")?;
+
+    for (name, id) in assets {
+        writeln!(output, "{},rbxassetid://{}", csv_field(name.as_ref()), id)?;
+    }
+
+    Ok(())
+}
+
+/// Quotes a CSV field if it contains anything that would otherwise break up
+/// the row, like a comma.
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn csv_rows_are_sorted_and_quoted() {
+        let close = AssetName::new("icons/close.png");
+        let odd = AssetName::new("icons/a, \"b\".png");
+
+        let mut output = Vec::new();
+        write_asset_csv(&mut output, vec![(&close, 5), (&odd, 7), (&close, 3)]).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "name,url\n\
+             \"icons/a, \"\"b\"\".png\",rbxassetid://7\n\
+             icons/close.png,rbxassetid://3\n\
+             icons/close.png,rbxassetid://5\n"
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use std::env;
+
+use crate::options::{CheckOptions, GlobalOptions};
+
+use super::sync::{describe_error, SyncSession};
+
+pub fn check(_global: GlobalOptions, options: CheckOptions) -> anyhow::Result<()> {
+    let fuzzy_config_path = match options.config_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let mut session = SyncSession::new(&fuzzy_config_path)?;
+
+    let errors = session.check_configs();
+
+    for error in &errors {
+        log::error!("{}", describe_error(error));
+    }
+
+    if !errors.is_empty() {
+        anyhow::bail!(
+            "Found {} problem(s) with this project's configs",
+            errors.len()
+        );
+    }
+
+    println!(
+        "Checked {} config(s), no problems found",
+        session.config_count()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use std::env;
+
+use crate::options::{CodegenOptions, GlobalOptions};
+
+use super::sync::SyncSession;
+
+pub fn codegen(_global: GlobalOptions, options: CodegenOptions) -> anyhow::Result<()> {
+    let fuzzy_config_path = match options.config_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let mut session = SyncSession::new(&fuzzy_config_path)?;
+
+    session.discover_configs()?;
+    session.discover_inputs_without_contents()?;
+
+    session.codegen()?;
+    session.check_codegen_coverage();
+    session.write_asset_list()?;
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::env;
+
+use anyhow::bail;
+use fs_err as fs;
+
+use crate::{
+    contact_sheet::contact_sheet as draw_contact_sheet,
+    image::PngCompression,
+    options::{ContactSheetOptions, GlobalOptions},
+};
+
+use super::sync::SyncSession;
+
+pub fn contact_sheet(_global: GlobalOptions, options: ContactSheetOptions) -> anyhow::Result<()> {
+    if options.thumbnail_size == 0 {
+        bail!("--thumbnail-size must be at least 1");
+    }
+
+    let fuzzy_config_path = match options.project_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let mut session = SyncSession::new(&fuzzy_config_path)?;
+
+    session.discover_configs()?;
+    session.discover_inputs()?;
+
+    let images = session.decode_images();
+    if images.is_empty() {
+        bail!("This Tarmac project has no images to draw");
+    }
+
+    let sheet = draw_contact_sheet(&images, options.thumbnail_size, options.columns);
+
+    let mut encoded = Vec::new();
+    sheet.encode_png_with_compression(&mut encoded, PngCompression::Default)?;
+    fs::write(&options.output, encoded)?;
+
+    log::info!(
+        "Wrote a contact sheet of {} images to {}",
+        images.len(),
+        options.output.display()
+    );
+
+    Ok(())
+}
@@ -5,23 +5,41 @@ use std::io::{BufWriter, Write};
 use fs_err as fs;
 
 use crate::asset_name::AssetName;
-use crate::data::Manifest;
+use crate::data::{Config, Manifest};
 use crate::options::{CreateCacheMapOptions, GlobalOptions};
 use crate::roblox_web_api::{RobloxApiClient, RobloxOpenCloudCredentials};
 
+use super::sync::write_cached;
+
 pub fn create_cache_map(
     global: GlobalOptions,
     options: CreateCacheMapOptions,
 ) -> anyhow::Result<()> {
     let credentials = RobloxOpenCloudCredentials::get_credentials(global.auth, global.api_key)?;
-    let mut api_client = RobloxApiClient::new(credentials);
+    let mut api_client = RobloxApiClient::builder(credentials)
+        .proxy(global.proxy)
+        .root_certificate(global.root_certificate)
+        .csrf_retry(!global.no_csrf_retry)
+        .build()?;
 
     let project_path = match options.project_path {
         Some(path) => path.clone(),
         None => env::current_dir()?,
     };
 
-    let manifest = Manifest::read_from_folder(&project_path)?;
+    // Projects can keep their manifest somewhere other than next to their
+    // config, so we need to check the config first.
+    let manifest = match Config::read_from_folder(&project_path) {
+        Ok(config) => Manifest::read_from_file(config.manifest_file_path())?,
+        Err(err) if err.is_not_found() => Manifest::read_from_folder(&project_path)?,
+        Err(err) => return Err(err.into()),
+    };
+
+    if !manifest.asset_name_style.is_path() {
+        log::warn!(
+            "This project's asset names aren't paths, so the cache map will contain names instead of paths"
+        );
+    }
 
     let index_dir = options.index_file.parent().unwrap();
     fs::create_dir_all(index_dir)?;
@@ -42,9 +60,9 @@ pub fn create_cache_map(
             index.insert(id, contributing_assets[0].to_string());
         } else {
             let contents = api_client.download_image(id)?;
-            let path = options.cache_dir.join(id.to_string());
-            fs::write(&path, contents)?;
+            write_cached(&options.cache_dir, id, &contents)?;
 
+            let path = options.cache_dir.join(id.to_string());
             index.insert(id, path.display().to_string());
         }
     }
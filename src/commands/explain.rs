@@ -0,0 +1,31 @@
+use std::env;
+
+use anyhow::bail;
+
+use crate::options::{ExplainOptions, GlobalOptions};
+
+use super::sync::SyncSession;
+
+pub fn explain(_global: GlobalOptions, options: ExplainOptions) -> anyhow::Result<()> {
+    let fuzzy_config_path = match options.project_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let mut session = SyncSession::new(&fuzzy_config_path)?;
+
+    session.discover_configs()?;
+    session.discover_inputs()?;
+
+    let name = match session.find_input_by_path(&options.path) {
+        Some(name) => name,
+        None => bail!(
+            "{} is not an input of this Tarmac project",
+            options.path.display()
+        ),
+    };
+
+    println!("{}", session.explain_input(name));
+
+    Ok(())
+}
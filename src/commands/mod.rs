@@ -1,9 +1,25 @@
+mod adopt;
 mod asset_list;
+mod check;
+mod codegen;
+mod contact_sheet;
 mod create_cache_map;
+mod explain;
+mod pack_preview;
+mod plan;
+mod populate_cache;
 mod sync;
 mod upload_image;
 
+pub use adopt::*;
 pub use asset_list::*;
+pub use check::*;
+pub use codegen::*;
+pub use contact_sheet::*;
 pub use create_cache_map::*;
+pub use explain::*;
+pub use pack_preview::*;
+pub use plan::*;
+pub use populate_cache::*;
 pub use sync::*;
 pub use upload_image::*;
@@ -0,0 +1,69 @@
+use std::env;
+use std::io::{BufWriter, Write};
+
+use fs_err as fs;
+
+use crate::options::{GlobalOptions, PackPreviewOptions};
+
+use super::sync::SyncSession;
+
+pub fn pack_preview(_global: GlobalOptions, options: PackPreviewOptions) -> anyhow::Result<()> {
+    let fuzzy_config_path = match options.config_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let mut session = SyncSession::new(&fuzzy_config_path)?;
+
+    session.discover_configs()?;
+    session.discover_inputs()?;
+
+    let previews = session.preview_spritesheets()?;
+
+    if let Some(output) = &options.output {
+        let mut file = BufWriter::new(fs::File::create(output)?);
+        serde_json::to_writer_pretty(&mut file, &previews)?;
+        file.flush()?;
+
+        return Ok(());
+    }
+
+    for (i, preview) in previews.iter().enumerate() {
+        let atlas = match &preview.atlas_group {
+            Some(atlas_group) => format!(", atlas {}", atlas_group),
+            None => String::new(),
+        };
+
+        println!(
+            "Spritesheet {} ({}x{}, {}x DPI{}, {} sprites)",
+            i + 1,
+            preview.size.0,
+            preview.size.1,
+            preview.dpi_scale,
+            atlas,
+            preview.sprites.len()
+        );
+
+        if let Some(packing) = &preview.packing {
+            if let Some(name) = &packing.resized_for {
+                println!(
+                    "  Grew {} time(s) from min-spritesheet-size to fit {}",
+                    packing.resizes, name
+                );
+            }
+
+            if let Some(name) = &packing.overflowed_with {
+                println!("  Full when {} didn't fit", name);
+            }
+        }
+
+        for (name, slice) in &preview.sprites {
+            let (x, y) = slice.min();
+            let (width, height) = slice.size();
+
+            println!("    {} at ({}, {}), {}x{}", name, x, y, width, height);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::env;
+
+use crate::options::{GlobalOptions, PlanOptions};
+
+use super::sync::SyncSession;
+
+pub fn plan(_global: GlobalOptions, options: PlanOptions) -> anyhow::Result<()> {
+    let fuzzy_config_path = match options.config_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let mut session = SyncSession::new(&fuzzy_config_path)?;
+
+    session.discover_configs()?;
+    session.discover_inputs()?;
+
+    let plan = session.plan_uploads()?;
+
+    println!(
+        "Images: {} ({} new, {} changed)",
+        plan.new_images + plan.changed_images,
+        plan.new_images,
+        plan.changed_images
+    );
+    println!(
+        "Spritesheets: {} ({} new, {} changed)",
+        plan.new_spritesheets + plan.changed_spritesheets,
+        plan.new_spritesheets,
+        plan.changed_spritesheets
+    );
+
+    if plan.duplicate_images > 0 {
+        println!(
+            "{} more image(s) have the same contents as another image and would reuse its upload",
+            plan.duplicate_images
+        );
+    }
+
+    if plan.reused_spritesheets > 0 {
+        println!(
+            "{} more spritesheet(s) are identical to an earlier upload and would reuse it",
+            plan.reused_spritesheets
+        );
+    }
+
+    println!("Total uploads: {}", plan.total_uploads());
+
+    Ok(())
+}
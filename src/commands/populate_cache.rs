@@ -0,0 +1,29 @@
+use std::env;
+
+use crate::options::{GlobalOptions, PopulateCacheOptions};
+use crate::roblox_web_api::{RobloxApiClient, RobloxOpenCloudCredentials};
+
+use super::sync::SyncSession;
+
+pub fn populate_cache(global: GlobalOptions, options: PopulateCacheOptions) -> anyhow::Result<()> {
+    let credentials = RobloxOpenCloudCredentials::get_credentials(global.auth, global.api_key)?;
+    let mut api_client = RobloxApiClient::builder(credentials)
+        .proxy(global.proxy)
+        .root_certificate(global.root_certificate)
+        .csrf_retry(!global.no_csrf_retry)
+        .build()?;
+
+    let fuzzy_config_path = match options.config_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let mut session = SyncSession::new(&fuzzy_config_path)?;
+
+    session.discover_configs()?;
+    session.discover_inputs_without_contents()?;
+
+    session.populate_asset_cache(&mut api_client)?;
+
+    Ok(())
+}
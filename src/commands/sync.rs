@@ -1,91 +1,226 @@
 use std::{
+    cell::RefCell,
     collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     env,
-    io::{self, BufWriter, Write},
+    io::{self, BufWriter, Read, Write},
     path::{Path, PathBuf},
-    time::Duration,
+    process::{Command, ExitStatus},
+    time::{Duration, Instant},
 };
 
 use fs_err as fs;
-use packos::{InputItem, SimplePacker};
+use packos::{InputItem, PackError, SimplePacker};
+use rayon::prelude::*;
+use serde::Serialize;
 use thiserror::Error;
 use walkdir::WalkDir;
 
 use crate::{
     alpha_bleed::alpha_bleed,
     asset_name::AssetName,
-    codegen::perform_codegen,
-    data::{Config, ConfigError, ImageSlice, InputManifest, Manifest, ManifestError, SyncInput},
-    dpi_scale,
-    image::Image,
+    cancel,
+    codegen::{codegen_index, inputs_missing_from_codegen, perform_codegen},
+    data::{
+        AssetListFormat, Config, ConfigError, IgnoreFile, IgnoreFileError, ImageSlice, ImageTile,
+        InputManifest, InputStatus, Manifest, ManifestError, Severity, SyncInput, TrimInfo,
+    },
+    dpi_scale::{self, PathInfoError},
+    image::{FileFormat, Image, Pixel, PngCompression, ResizeFilter},
+    layout_svg::layout_svg,
     options::{GlobalOptions, SyncOptions, SyncTarget},
     roblox_web_api::{RobloxApiClient, RobloxApiError, RobloxOpenCloudCredentials},
     roblox_web_api_types::RobloxAuthenticationError,
     sync_backend::{
         DebugSyncBackend, Error as SyncBackendError, NoneSyncBackend, RetryBackend,
-        RobloxSyncBackend, SyncBackend, UploadInfo,
+        RobloxSyncBackend, SyncBackend, UploadInfo, UploadResponse,
     },
+    timings::{Stage, Timings},
 };
 
+use super::asset_list::write_asset_csv;
+
 fn sync_session<B: SyncBackend>(session: &mut SyncSession, options: &SyncOptions, mut backend: B) {
+    // Only uploading is worth stopping gracefully for. Everything before it
+    // can be thrown away, and everything after it is quick.
+    let _uploading = cancel::while_uploading();
+
     if let Some(retry) = options.retry {
         let mut retry_backend =
-            RetryBackend::new(backend, retry, Duration::from_secs(options.retry_delay));
+            RetryBackend::new(backend, retry, Duration::from_secs(options.retry_delay))
+                .with_max_delay(
+                    Duration::from_secs(options.retry_max_delay),
+                    options.retry_fail_over_max_delay,
+                );
         session.sync_with_backend(&mut retry_backend);
     } else {
         session.sync_with_backend(&mut backend);
     }
 }
 
+/// Reads the config that a sync starts from, which can come from a file, a
+/// folder, stdin, or the command line.
+fn read_root_config(options: &SyncOptions) -> Result<Config, SyncError> {
+    // Configs that aren't on disk resolve relative paths from the current
+    // directory. Their names only show up in error messages.
+    if let Some(contents) = &options.config_inline {
+        let file_path = env::current_dir()?.join("<inline config>");
+        return Ok(Config::from_slice(contents.as_bytes(), file_path)?);
+    }
+
+    match &options.config_path {
+        Some(path) if path.as_os_str() == "-" => {
+            let mut contents = Vec::new();
+            io::stdin().read_to_end(&mut contents)?;
+
+            let file_path = env::current_dir()?.join("<stdin>");
+            Ok(Config::from_slice(&contents, file_path)?)
+        }
+        Some(path) => Ok(Config::read_from_folder_or_file(path)?),
+        None => Ok(Config::read_from_folder(env::current_dir()?)?),
+    }
+}
+
 pub fn sync(global: GlobalOptions, options: SyncOptions) -> Result<(), SyncError> {
-    let fuzzy_config_path = match &options.config_path {
-        Some(v) => v.to_owned(),
-        None => env::current_dir()?,
-    };
+    let root_config = read_root_config(&options)?;
 
     let credentials = RobloxOpenCloudCredentials::get_credentials(global.auth, global.api_key)?;
-    let mut api_client = RobloxApiClient::new(credentials);
+    let mut api_client = RobloxApiClient::builder(credentials)
+        .proxy(global.proxy)
+        .root_certificate(global.root_certificate)
+        .csrf_retry(!global.no_csrf_retry)
+        .build()?;
+
+    // A key that can't upload would otherwise only fail at the first upload,
+    // after discovering and packing everything.
+    if let SyncTarget::Roblox = options.target {
+        api_client.check_api_key()?;
+    }
+
+    let mut session = SyncSession::from_config(root_config)?;
+    session.fail_fast = options.fail_fast;
+    session.bleed_jobs = options.bleed_jobs;
+
+    if let Some(folder) = &options.dump_spritesheets {
+        session.spritesheet_dump = Some(SpritesheetDump {
+            folder: folder.clone(),
+            outline_color: options.dump_outline_color,
+            count: 0,
+        });
+    }
 
-    let mut session = SyncSession::new(&fuzzy_config_path)?;
+    if let Some(folder) = &options.layout_svg {
+        session.layout_svg_dump = Some(LayoutSvgDump {
+            folder: folder.clone(),
+            count: 0,
+        });
+    }
 
     session.discover_configs()?;
+
+    if !options.only.is_empty() {
+        session.select_configs(&options.only)?;
+    }
+
     session.discover_inputs()?;
 
+    // Syncing without any inputs would throw away everything in the manifest,
+    // which is almost never what the user wanted.
+    if let Err(err) = session.check_inputs_found() {
+        if options.error_on_empty {
+            return Err(err);
+        }
+
+        log::warn!("{}", err);
+    }
+
+    session.validate_dpi_variants();
+    session.check_unrecognized_inputs();
+    session.check_image_formats();
+    session.check_fail_fast()?;
+
     match &options.target {
         SyncTarget::Roblox => {
-            let group_id = session.root_config().upload_to_group_id;
+            // Creators given on the command line take priority over the one
+            // from the project's config.
+            let (user_id, group_id) = match (options.user_id, options.group_id) {
+                (None, None) => (None, session.root_config().upload_to_group_id),
+                creator => creator,
+            };
+
             sync_session(
                 &mut session,
                 &options,
-                RobloxSyncBackend::new(&mut api_client, group_id),
+                RobloxSyncBackend::new(&mut api_client, user_id, group_id),
             );
         }
         SyncTarget::None => {
             sync_session(&mut session, &options, NoneSyncBackend);
         }
         SyncTarget::Debug => {
-            sync_session(&mut session, &options, DebugSyncBackend::new());
+            sync_session(
+                &mut session,
+                &options,
+                DebugSyncBackend::new(options.debug_output.clone()),
+            );
         }
     }
 
+    // Progress was already saved when the sync stopped.
+    session.check_fail_fast()?;
+
+    // Ctrl-C could have been pressed after the last upload, in which case
+    // this is the first time that the sync notices.
+    if session.check_canceled() {
+        return Err(SyncError::Canceled);
+    }
+
+    if options.require_approved && !session.unapproved_uploads.is_empty() {
+        let uploads = session.unapproved_uploads.clone();
+        session.raise_error(SyncError::UnapprovedUploads { uploads });
+    }
+
     session.write_manifest()?;
-    session.codegen()?;
+
+    if !options.no_codegen {
+        session.codegen()?;
+        session.check_codegen_coverage();
+    }
+
     session.write_asset_list()?;
     session.populate_asset_cache(&mut api_client)?;
 
-    if session.sync_errors.is_empty() {
-        Ok(())
-    } else {
-        Err(SyncError::HadErrors {
+    let report = session.timings.borrow().report();
+    if let Some(path) = &options.timings_output {
+        fs::write(path, report)?;
+    } else if options.timings {
+        print!("{}", report);
+    }
+
+    // These are called out on their own since, unlike most errors, they
+    // won't go away by syncing again.
+    if !session.rejected_uploads.is_empty() {
+        log::error!(
+            "{} asset(s) were rejected by Roblox, like by moderation, and need to be changed \
+             before they can be uploaded: {}",
+            session.rejected_uploads.len(),
+            session.rejected_uploads.join(", ")
+        );
+    }
+
+    if !session.sync_errors.is_empty() {
+        return Err(SyncError::HadErrors {
             error_count: session.sync_errors.len(),
-        })
+        });
     }
+
+    // Everything is written by now, so the command sees the finished sync.
+    session.run_post_sync_command(options.target)
 }
 
 /// A sync session holds all of the state for a single run of the 'tarmac sync'
 /// command.
 #[derive(Debug)]
-struct SyncSession {
+pub(super) struct SyncSession {
     /// The set of all configs known by the sync session.
     ///
     /// This list is always at least one element long. The first entry is the
@@ -97,44 +232,293 @@ struct SyncSession {
     /// operation.
     original_manifest: Manifest,
 
+    /// The asset IDs of spritesheets uploaded in this sync or an earlier one,
+    /// keyed by `spritesheet_pixel_hash`.
+    spritesheet_ids: BTreeMap<String, u64>,
+
+    /// The asset IDs of spritesheets uploaded in this sync or an earlier one,
+    /// keyed by the hash of their encoded PNG files.
+    spritesheet_hashes: BTreeMap<String, u64>,
+
     /// All of the inputs discovered so far in the current sync.
     inputs: BTreeMap<AssetName, SyncInput>,
 
     /// Errors encountered during syncing that we ignored at the time.
     sync_errors: Vec<anyhow::Error>,
+
+    /// Whether the sync should stop at the first error instead of carrying on
+    /// and reporting every error at the end.
+    fail_fast: bool,
+
+    /// The most spritesheets to alpha bleed at the same time, or `None` for
+    /// one for each CPU.
+    bleed_jobs: Option<usize>,
+
+    /// The indices of the configs whose inputs should be synced, if only some
+    /// of them should be.
+    selected_configs: Option<HashSet<usize>>,
+
+    /// Inputs from configs that weren't selected. Their contents aren't read,
+    /// so they're never uploaded, and the manifest keeps what it knew about
+    /// them from the last sync.
+    frozen: HashSet<AssetName>,
+
+    /// Inputs whose changes haven't been uploaded yet during this sync. Until
+    /// they are, the manifest keeps what it knew about them from the last sync
+    /// so that they aren't mistaken for being up to date.
+    unsynced: HashSet<AssetName>,
+
+    /// Why uploading each of these inputs failed during this sync, which is
+    /// recorded in the manifest.
+    upload_errors: HashMap<AssetName, String>,
+
+    /// The number of uploads that have completed since we last saved the
+    /// manifest.
+    uploads_since_manifest_write: usize,
+
+    /// Whether the sync was stopped with Ctrl-C.
+    canceled: bool,
+
+    /// The number of assets uploaded during this sync, not counting ones that
+    /// reused an existing asset.
+    upload_count: usize,
+
+    /// Descriptions of assets uploaded during this sync that have not been
+    /// approved by moderation yet.
+    unapproved_uploads: Vec<String>,
+
+    /// The names of assets that Roblox rejected during this sync, like for
+    /// failing moderation. Unlike other errors, retrying won't help these.
+    rejected_uploads: Vec<String>,
+
+    /// The IDs of everything uploaded during this sync, keyed by the hash of
+    /// their contents. Spritesheets and individual images that have the exact
+    /// same contents only need to be uploaded once. Starts out with the
+    /// spritesheets known from earlier syncs.
+    uploaded_by_hash: HashMap<String, u64>,
+
+    /// The tiles uploaded so far for images that are being split into tiles,
+    /// keyed by the hash of their encoded PNG files. These are saved to the
+    /// manifest if the sync stops before the rest of an image's tiles are
+    /// uploaded.
+    partial_tiles: BTreeMap<AssetName, BTreeMap<String, u64>>,
+
+    /// How long each stage of the sync took for each asset. Methods that only
+    /// borrow the session record timings too, so this is kept in a RefCell.
+    timings: RefCell<Timings>,
+
+    /// Where to write outlined copies of packed spritesheets, if anywhere.
+    spritesheet_dump: Option<SpritesheetDump>,
+
+    /// Where to write SVGs of packed spritesheet layouts, if anywhere.
+    layout_svg_dump: Option<LayoutSvgDump>,
+}
+
+/// Describes how `--dump-spritesheets` should write out spritesheets.
+#[derive(Debug)]
+struct SpritesheetDump {
+    folder: PathBuf,
+    outline_color: Pixel,
+
+    /// The number of spritesheets dumped so far, used to name the files.
+    count: usize,
+}
+
+/// Describes where `--layout-svg` should write spritesheet layouts.
+#[derive(Debug)]
+struct LayoutSvgDump {
+    folder: PathBuf,
+
+    /// The number of layouts written so far, used to name the files.
+    count: usize,
 }
 
+/// How many uploads can happen before Tarmac saves its progress to the
+/// manifest, in case the sync is interrupted.
+const MANIFEST_WRITE_INTERVAL: usize = 10;
+
+/// The largest image size that Roblox supports, used for spritesheets when no
+/// config sets `max-spritesheet-size`.
+const DEFAULT_MAX_SPRITESHEET_SIZE: (u32, u32) = (1024, 1024);
+
+/// Groups that pack into at least this many spritesheets, covering less than
+/// `LOW_SPRITESHEET_OCCUPANCY` of their area, get a warning that their
+/// settings might be wasting uploads.
+const MANY_SPRITESHEETS: usize = 4;
+const LOW_SPRITESHEET_OCCUPANCY: f64 = 0.25;
+
+/// The size that spritesheets start at when no config sets
+/// `min-spritesheet-size`, which is also Packos's default.
+const DEFAULT_MIN_SPRITESHEET_SIZE: (u32, u32) = (128, 128);
+
 /// Contains information to help Tarmac batch process different kinds of assets.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+///
+/// Inputs are only packed into the same spritesheets when their kinds are
+/// equal, so any setting that changes how a spritesheet is built must be part
+/// of this struct. Otherwise, one input group's settings would silently apply
+/// to another group's images.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct InputKind {
     packable: bool,
     dpi_scale: u32,
+
+    /// The atlas that packable inputs were put in by their config. Inputs with
+    /// no atlas all share one.
+    atlas_group: Option<String>,
+
+    /// Spritesheet settings from the config that owns the input, falling back
+    /// to the root config's.
+    max_spritesheet_size: (u32, u32),
+    min_spritesheet_size: (u32, u32),
+    spritesheet_background: (u8, u8, u8, u8),
+}
+
+impl InputKind {
+    fn new(input: &SyncInput, owner: &Config, root: &Config) -> Self {
+        Self {
+            packable: input.config.packable,
+            dpi_scale: input.dpi_scale,
+            atlas_group: if input.config.packable {
+                input.config.atlas_group.clone()
+            } else {
+                None
+            },
+            max_spritesheet_size: owner
+                .max_spritesheet_size
+                .or(root.max_spritesheet_size)
+                .unwrap_or(DEFAULT_MAX_SPRITESHEET_SIZE),
+            min_spritesheet_size: owner
+                .min_spritesheet_size
+                .or(root.min_spritesheet_size)
+                .unwrap_or(DEFAULT_MIN_SPRITESHEET_SIZE),
+            spritesheet_background: owner
+                .spritesheet_background
+                .or(root.spritesheet_background)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The layout of a spritesheet that Tarmac would create during a sync.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(super) struct SpritesheetPreview {
+    pub dpi_scale: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub atlas_group: Option<String>,
+    pub size: (u32, u32),
+    pub sprites: BTreeMap<AssetName, ImageSlice>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub packing: Option<PackingDetails>,
+}
+
+/// Explains how a spritesheet ended up at its size and why images that could
+/// have been in it weren't, for diagnosing groups that use more spritesheets
+/// than expected.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(super) struct PackingDetails {
+    /// How many times the spritesheet doubled in size from
+    /// `min-spritesheet-size`.
+    pub resizes: u32,
+
+    /// The image that didn't fit before the spritesheet's last resize.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resized_for: Option<AssetName>,
+
+    /// The first image that didn't fit in this spritesheet and went into
+    /// another one instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overflowed_with: Option<AssetName>,
+}
+
+/// How many uploads the next sync would make, as reported by `tarmac plan`.
+#[derive(Debug, Default)]
+pub(super) struct UploadPlan {
+    pub new_images: usize,
+    pub changed_images: usize,
+
+    /// Images that would reuse the upload of another image with the same
+    /// contents.
+    pub duplicate_images: usize,
+
+    pub new_spritesheets: usize,
+    pub changed_spritesheets: usize,
+
+    /// Spritesheets that would reuse an earlier upload with the same contents,
+    /// or with the same pixels because of `hash-spritesheet-pixels`.
+    pub reused_spritesheets: usize,
+}
+
+impl UploadPlan {
+    pub fn total_uploads(&self) -> usize {
+        self.new_images + self.changed_images + self.new_spritesheets + self.changed_spritesheets
+    }
 }
 
 struct PackedImage {
     image: Image,
     slices: HashMap<AssetName, ImageSlice>,
+    trim_infos: HashMap<AssetName, TrimInfo>,
+
+    /// How the spritesheet was packed, if it was packed from scratch.
+    packing: Option<PackingDetails>,
 }
 
 impl SyncSession {
-    fn new(fuzzy_config_path: &Path) -> Result<Self, SyncError> {
-        log::trace!("Starting new sync session");
+    pub(super) fn new(fuzzy_config_path: &Path) -> Result<Self, SyncError> {
+        let root_config = Config::read_from_folder_or_file(fuzzy_config_path)?;
+
+        Self::from_config(root_config)
+    }
 
-        let root_config = Config::read_from_folder_or_file(&fuzzy_config_path)?;
+    /// Starts a sync session from a config that has already been read.
+    pub(super) fn from_config(root_config: Config) -> Result<Self, SyncError> {
+        log::trace!("Starting new sync session");
 
         log::trace!("Starting from config \"{}\"", root_config.name);
 
-        let original_manifest = match Manifest::read_from_folder(root_config.folder()) {
+        let original_manifest = match Manifest::read_from_file(root_config.manifest_file_path()) {
             Ok(manifest) => manifest,
             Err(err) if err.is_not_found() => Manifest::default(),
             other => other?,
         };
 
+        let spritesheet_ids = original_manifest.spritesheets.clone();
+        let spritesheet_hashes = original_manifest.spritesheet_hashes.clone();
+        let partial_tiles = original_manifest
+            .inputs
+            .values()
+            .flat_map(|input| &input.partial_tiles);
+        let uploaded_by_hash = spritesheet_hashes
+            .iter()
+            .chain(partial_tiles)
+            .map(|(hash, &id)| (hash.clone(), id))
+            .collect();
+
         Ok(Self {
             configs: vec![root_config],
             original_manifest,
+            spritesheet_ids,
+            spritesheet_hashes,
             inputs: BTreeMap::new(),
             sync_errors: Vec::new(),
+            fail_fast: false,
+            bleed_jobs: None,
+            selected_configs: None,
+            frozen: HashSet::new(),
+            unsynced: HashSet::new(),
+            upload_errors: HashMap::new(),
+            uploads_since_manifest_write: 0,
+            upload_count: 0,
+            canceled: false,
+            unapproved_uploads: Vec::new(),
+            rejected_uploads: Vec::new(),
+            uploaded_by_hash,
+            partial_tiles: BTreeMap::new(),
+            timings: RefCell::new(Timings::new()),
+            spritesheet_dump: None,
+            layout_svg_dump: None,
         })
     }
 
@@ -145,6 +529,18 @@ impl SyncSession {
         self.sync_errors.push(error);
     }
 
+    /// With `--fail-fast`, turns the first error raised so far into the
+    /// result of the whole sync.
+    fn check_fail_fast(&mut self) -> Result<(), SyncError> {
+        if self.fail_fast && !self.sync_errors.is_empty() {
+            return Err(SyncError::FailFast {
+                source: self.sync_errors.remove(0),
+            });
+        }
+
+        Ok(())
+    }
+
     /// The config that this sync session was started from.
     fn root_config(&self) -> &Config {
         &self.configs[0]
@@ -155,80 +551,226 @@ impl SyncSession {
     /// Tarmac config files can include each other via the `includes` field,
     /// which will search the given path for other config files and use them as
     /// part of the sync.
-    fn discover_configs(&mut self) -> Result<(), SyncError> {
+    pub(super) fn discover_configs(&mut self) -> Result<(), SyncError> {
+        match self.find_configs(true).pop() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Locates every config connected to our root config like
+    /// `discover_configs`, but keeps going after a config fails to load and
+    /// returns every error found. Configs included by one that failed to load
+    /// can't be found.
+    pub(super) fn check_configs(&mut self) -> Vec<SyncError> {
+        self.find_configs(false)
+    }
+
+    /// The number of configs that have been found, including the root config.
+    pub(super) fn config_count(&self) -> usize {
+        self.configs.len()
+    }
+
+    fn find_configs(&mut self, stop_at_first_error: bool) -> Vec<SyncError> {
         let mut to_search = VecDeque::new();
         to_search.extend(self.root_config().includes.iter().cloned());
 
+        let mut errors = Vec::new();
+
         while let Some(search_path) = to_search.pop_front() {
-            let search_meta = fs::metadata(&search_path)?;
+            if let Err(err) = self.search_for_configs(&search_path, &mut to_search) {
+                errors.push(err);
+
+                if stop_at_first_error {
+                    break;
+                }
+            }
+        }
 
-            if search_meta.is_file() {
-                // This is a file that's explicitly named by a config. We'll
-                // check that it's a Tarmac config and include it.
+        errors
+    }
 
-                let config = Config::read_from_file(&search_path)?;
+    /// Includes the config at the given path, or looks for configs in it if
+    /// it's a folder. Paths that still need to be searched are added to
+    /// `to_search`.
+    fn search_for_configs(
+        &mut self,
+        search_path: &Path,
+        to_search: &mut VecDeque<PathBuf>,
+    ) -> Result<(), SyncError> {
+        let search_meta = fs::metadata(search_path)?;
 
-                // Include any configs that this config references.
-                to_search.extend(config.includes.iter().cloned());
+        if search_meta.is_file() {
+            // This is a file that's explicitly named by a config. We'll
+            // check that it's a Tarmac config and include it.
 
-                self.configs.push(config);
-            } else {
-                // If this directory contains a config file, we can stop
-                // traversing this branch.
+            let config = Config::read_from_file(search_path)?;
 
-                match Config::read_from_folder(&search_path) {
-                    Ok(config) => {
-                        // We found a config, we're done here.
+            // Include any configs that this config references.
+            to_search.extend(config.includes.iter().cloned());
 
-                        // Append config include paths from this config
-                        to_search.extend(config.includes.iter().cloned());
+            self.configs.push(config);
+        } else {
+            // If this directory contains a config file, we can stop
+            // traversing this branch.
 
-                        self.configs.push(config);
-                    }
+            match Config::read_from_folder(search_path) {
+                Ok(config) => {
+                    // We found a config, we're done here.
 
-                    Err(err) if err.is_not_found() => {
-                        // We didn't find a config, keep searching down this
-                        // branch of the filesystem.
+                    // Append config include paths from this config
+                    to_search.extend(config.includes.iter().cloned());
 
-                        let children = fs::read_dir(&search_path)?;
+                    self.configs.push(config);
+                }
 
-                        for entry in children {
-                            let entry = entry?;
-                            let entry_path = entry.path();
+                Err(err) if err.is_not_found() => {
+                    // We didn't find a config, keep searching down this
+                    // branch of the filesystem.
 
-                            // DirEntry has a metadata method, but in the case
-                            // of symlinks, it returns metadata about the
-                            // symlink and not the file or folder.
-                            let entry_meta = fs::metadata(&entry_path)?;
+                    let children = fs::read_dir(search_path)?;
 
-                            if entry_meta.is_dir() {
-                                to_search.push_back(entry_path);
-                            }
+                    for entry in children {
+                        let entry = entry?;
+                        let entry_path = entry.path();
+
+                        // DirEntry has a metadata method, but in the case
+                        // of symlinks, it returns metadata about the
+                        // symlink and not the file or folder.
+                        let entry_meta = fs::metadata(&entry_path)?;
+
+                        if entry_meta.is_dir() {
+                            to_search.push_back(entry_path);
                         }
                     }
+                }
 
-                    Err(err) => {
-                        return Err(err.into());
-                    }
+                Err(err) => {
+                    return Err(err.into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Limits the sync to inputs from the configs with the given names or
+    /// paths. Inputs from other configs are still found so that codegen and the
+    /// manifest stay complete, but their contents aren't read.
+    pub(super) fn select_configs(&mut self, names_or_paths: &[String]) -> Result<(), SyncError> {
+        let mut selected = HashSet::new();
+
+        for name_or_path in names_or_paths {
+            let path = fs::canonicalize(name_or_path).ok();
+
+            let mut found = false;
+            for (index, config) in self.configs.iter().enumerate() {
+                let matches_path = path.is_some()
+                    && [config.file_path.as_path(), config.folder()]
+                        .iter()
+                        .any(|config_path| fs::canonicalize(config_path).ok() == path);
+
+                if config.name == *name_or_path || matches_path {
+                    selected.insert(index);
+                    found = true;
                 }
             }
+
+            if !found {
+                return Err(SyncError::UnknownConfig {
+                    name: name_or_path.clone(),
+                });
+            }
         }
 
+        self.selected_configs = Some(selected);
         Ok(())
     }
 
     /// Find all files on the filesystem referenced as inputs by our configs.
-    fn discover_inputs(&mut self) -> Result<(), SyncError> {
+    pub(super) fn discover_inputs(&mut self) -> Result<(), SyncError> {
+        self.find_inputs(true)?;
+        self.read_inputs_sharing_spritesheets()
+    }
+
+    /// Packed images are uploaded together, so inputs from configs that
+    /// weren't selected can't stay untouched if they share spritesheets with
+    /// inputs that were. Those inputs are read like any other.
+    fn read_inputs_sharing_spritesheets(&mut self) -> Result<(), SyncError> {
+        if self.frozen.is_empty() {
+            return Ok(());
+        }
+
+        let mut shared = Vec::new();
+        for (kind, group) in self.group_inputs_by_kind() {
+            let frozen_count = group
+                .iter()
+                .filter(|name| self.frozen.contains(name))
+                .count();
+
+            if kind.packable && frozen_count > 0 && frozen_count < group.len() {
+                shared.extend(group.into_iter().filter(|name| self.frozen.contains(name)));
+            }
+        }
+
+        if shared.is_empty() {
+            return Ok(());
+        }
+
+        log::warn!(
+            "{} packable input(s) from configs that weren't selected share spritesheets with \
+             selected inputs, so they will be synced too:",
+            shared.len()
+        );
+
+        for name in shared {
+            log::warn!("  {}", name);
+
+            let input = self.inputs.get_mut(&name).unwrap();
+            input.contents = fs::read(&input.path)?;
+            input.hash = generate_asset_hash(&input.contents);
+            input.dimensions = Image::read_dimensions(input.contents.as_slice()).ok();
+
+            self.frozen.remove(&name);
+        }
+
+        Ok(())
+    }
+
+    /// Find all inputs referenced by our configs like `discover_inputs`, but
+    /// without reading their contents.
+    ///
+    /// Inputs found this way assume that they haven't changed since the last
+    /// sync, and so can only be used for work that doesn't need their
+    /// contents, like codegen.
+    pub(super) fn discover_inputs_without_contents(&mut self) -> Result<(), SyncError> {
+        self.find_inputs(false)
+    }
+
+    fn find_inputs(&mut self, read_contents: bool) -> Result<(), SyncError> {
         let inputs = &mut self.inputs;
+        let frozen = &mut self.frozen;
         let root_config_path = &self.configs[0].folder();
+        let name_style = self.configs[0].asset_name_style;
+        let root_config = &self.configs[0];
+
+        // What the last sync knew about each input, keyed by its current name.
+        let mut renamed_originals = BTreeMap::new();
 
         // Starting with our root config, iterate over all configs and find all
         // relevant inputs
-        for config in &self.configs {
+        for (config_index, config) in self.configs.iter().enumerate() {
+            let is_selected = self
+                .selected_configs
+                .as_ref()
+                .map_or(true, |selected| selected.contains(&config_index));
+            let read_contents = read_contents && is_selected;
+
             let config_path = config.folder();
+            let ignore_file = IgnoreFile::read_from_folder(config_path)?;
 
             for input_config in &config.inputs {
-                let base_path = config_path.join(input_config.glob.get_prefix());
+                let base_path = input_config.glob.get_walk_root(config_path);
                 log::trace!(
                     "Searching for inputs in '{}' matching '{}'",
                     base_path.display(),
@@ -240,26 +782,106 @@ impl SyncSession {
                     // TODO: Properly handle WalkDir errors
                     .filter_map(Result::ok)
                     .filter(|entry| {
-                        let match_path = entry.path().strip_prefix(config_path).unwrap();
-                        input_config.glob.is_match(match_path)
+                        // Absolute globs are matched against whole paths,
+                        // since they don't need to live inside the config's
+                        // folder.
+                        let is_match = if input_config.glob.is_absolute() {
+                            input_config.glob.is_match(entry.path())
+                        } else {
+                            let match_path = entry.path().strip_prefix(config_path).unwrap();
+                            input_config.glob.is_match(match_path)
+                        };
+
+                        // Ignore files win over input globs.
+                        let is_ignored = is_match
+                            && entry
+                                .path()
+                                .strip_prefix(config_path)
+                                .map(|path| ignore_file.is_ignored(path))
+                                .unwrap_or(false);
+
+                        if is_ignored {
+                            log::trace!("Ignoring input {}", entry.path().display());
+                        }
+
+                        is_match && !is_ignored
                     });
 
                 for matching in filtered_paths {
                     let path = matching.into_path();
 
-                    let name = AssetName::from_paths(&root_config_path, &path);
+                    let name = AssetName::from_paths(root_config_path, &path, name_style);
                     log::trace!("Found input {}", name);
 
-                    let path_info = dpi_scale::extract_path_info(&path);
+                    let path_info = dpi_scale::extract_path_info(&path)?;
 
-                    let contents = fs::read(&path)?;
-                    let hash = generate_asset_hash(&contents);
+                    // Folder conventions are more specific than the input's
+                    // `packable` flag, so they win.
+                    let mut input_config = input_config.clone();
+                    if let Some(packable) = config.packable_by_folder(&path) {
+                        input_config.packable = packable;
+                    }
 
                     // If this input was known during the last sync operation,
-                    // pull the information we knew about it out.
-                    let (id, slice) = match self.original_manifest.inputs.get(&name) {
-                        Some(original) => (original.id, original.slice),
-                        None => (None, None),
+                    // pull the information we knew about it out. The last sync
+                    // might have named it differently.
+                    let original_name = AssetName::from_paths(
+                        root_config_path,
+                        &path,
+                        self.original_manifest.asset_name_style,
+                    );
+                    let original = self.original_manifest.inputs.get(&original_name);
+                    if let Some(original) = original {
+                        renamed_originals.insert(name.clone(), original.clone());
+                    }
+
+                    // Inputs that weren't selected are only kept around for
+                    // what the last sync knew about them. New ones can wait
+                    // until they're synced.
+                    if !is_selected {
+                        if original.is_none() {
+                            log::debug!("Skipping new input {} from unselected config", name);
+                            continue;
+                        }
+
+                        frozen.insert(name.clone());
+                    }
+                    let (id, slice, trim_info, tiles) = match original {
+                        Some(original) => (
+                            original.id,
+                            original.slice,
+                            original.trim_info,
+                            original.tiles.clone(),
+                        ),
+                        None => (None, None, None, Vec::new()),
+                    };
+
+                    let (contents, hash) = if read_contents {
+                        let mut timings = self.timings.borrow_mut();
+
+                        let start = Instant::now();
+                        let contents = fs::read(&path)?;
+                        timings.record(name.to_string(), Stage::Read, start.elapsed());
+
+                        let start = Instant::now();
+                        let hash = generate_asset_hash(&contents);
+                        timings.record(name.to_string(), Stage::Hash, start.elapsed());
+
+                        (contents, hash)
+                    } else {
+                        let hash = original
+                            .map(|original| original.hash.clone())
+                            .unwrap_or_default();
+                        (Vec::new(), hash)
+                    };
+
+                    // Only the image's header is read here, so this is cheap
+                    // even for projects with lots of large images. If it can't
+                    // be read, decoding will fail later with a better error.
+                    let dimensions = if read_contents && root_config.is_image_path(&path) {
+                        Image::read_dimensions(contents.as_slice()).ok()
+                    } else {
+                        None
                     };
 
                     let already_found = inputs.insert(
@@ -269,126 +891,512 @@ impl SyncSession {
                             path,
                             path_without_dpi_scale: path_info.path_without_dpi_scale,
                             dpi_scale: path_info.dpi_scale,
-                            config: input_config.clone(),
+                            config: input_config,
+                            config_index,
                             contents,
                             hash,
+                            dimensions,
                             id,
                             slice,
+                            trim_info,
+                            tiles,
                         },
                     );
 
                     if let Some(existing) = already_found {
-                        return Err(SyncError::OverlappingGlobs {
-                            path: existing.path,
-                        });
+                        let path = &inputs[&existing.name].path;
+
+                        if existing.path == *path {
+                            return Err(SyncError::OverlappingGlobs {
+                                path: existing.path,
+                            });
+                        } else {
+                            return Err(SyncError::DuplicateAssetName {
+                                name: existing.name.to_string(),
+                                first: existing.path,
+                                second: path.clone(),
+                            });
+                        }
                     }
                 }
             }
         }
 
+        // If the asset name style changed since the last sync, re-key what we
+        // knew about each input so that the rest of the sync sees it under its
+        // new name. Inputs that no longer exist are dropped, like they would
+        // be when writing the manifest anyway.
+        if self.original_manifest.asset_name_style != name_style {
+            log::info!(
+                "Asset name style changed from {:?} to {:?}, renaming inputs in the manifest",
+                self.original_manifest.asset_name_style,
+                name_style
+            );
+
+            self.original_manifest.asset_name_style = name_style;
+            self.original_manifest.inputs = renamed_originals;
+        }
+
         Ok(())
     }
 
-    fn sync_with_backend<S: SyncBackend>(&mut self, backend: &mut S) {
-        let mut compatible_input_groups = BTreeMap::new();
-
-        for (input_name, input) in &self.inputs {
-            if !is_image_asset(&input.path) {
-                log::warn!(
-                    "Asset '{}' is not recognized by Tarmac.",
-                    input.path.display()
-                );
+    /// Check that every image with multiple DPI variants has variants whose
+    /// sizes agree with their DPI scales. For example, `logo@2x.png` should be
+    /// exactly twice as large as `logo.png`.
+    ///
+    /// Mismatched variants cause sprites to show up at the wrong size at
+    /// runtime, so we either warn or raise an error depending on the config.
+    fn validate_dpi_variants(&mut self) {
+        let mut variants_by_path: BTreeMap<&Path, Vec<&SyncInput>> = BTreeMap::new();
 
+        for input in self.inputs.values() {
+            if !self.root_config().is_image_path(&input.path) {
                 continue;
             }
 
-            let kind = InputKind {
-                packable: input.config.packable,
-                dpi_scale: input.dpi_scale,
-            };
-
-            let input_group = compatible_input_groups.entry(kind).or_insert_with(Vec::new);
-
-            input_group.push(input_name.clone());
+            variants_by_path
+                .entry(&input.path_without_dpi_scale)
+                .or_default()
+                .push(input);
         }
 
-        'outer: for (kind, group) in compatible_input_groups {
-            if kind.packable {
-                if let Err(err) = self.sync_packable_images(backend, group) {
-                    let rate_limited = err.is_rate_limited();
-
-                    println!("{}: {:#?}", rate_limited, err);
-
-                    self.raise_error(err);
+        let mut mismatches = Vec::new();
 
-                    if rate_limited {
-                        break 'outer;
-                    }
-                }
-            } else {
-                for input_name in group {
-                    if let Err(err) = self.sync_unpackable_image(backend, &input_name) {
-                        let rate_limited = err.is_rate_limited();
+        for variants in variants_by_path.values() {
+            if variants.len() < 2 {
+                continue;
+            }
 
-                        self.raise_error(err);
+            // If we can't read a variant's size, it will fail to sync later
+            // with a better error, so we don't need to report it here.
+            let mut sizes: Vec<_> = variants
+                .iter()
+                .filter_map(|input| Some((input.dpi_scale, input.dimensions?, &input.path)))
+                .collect();
+            sizes.sort_by_key(|&(dpi_scale, _, _)| dpi_scale);
+
+            let mut sizes = sizes.into_iter();
+            let (base_scale, base_size, base_path) = match sizes.next() {
+                Some(base) => base,
+                None => continue,
+            };
 
-                        if rate_limited {
-                            break 'outer;
-                        }
-                    }
+            for (dpi_scale, size, path) in sizes {
+                let matches = size.0 * base_scale == base_size.0 * dpi_scale
+                    && size.1 * base_scale == base_size.1 * dpi_scale;
+
+                if !matches {
+                    mismatches.push(SyncError::DpiScaleMismatch {
+                        base_path: base_path.clone(),
+                        base_scale,
+                        base_size,
+                        path: path.clone(),
+                        dpi_scale,
+                        size,
+                    });
                 }
             }
         }
 
-        // TODO: Clean up output of inputs that were present in the previous
-        // sync but are no longer present.
+        for mismatch in mismatches {
+            match self.root_config().dpi_scale_mismatch {
+                Severity::Warn => log::warn!("{}", mismatch),
+                Severity::Error => self.raise_error(mismatch),
+            }
+        }
     }
 
-    fn sync_packable_images<S: SyncBackend>(
-        &mut self,
-        backend: &mut S,
-        group: Vec<AssetName>,
-    ) -> Result<(), SyncError> {
-        if self.are_inputs_unchanged(&group) {
-            log::info!("Skipping image packing as all inputs are unchanged.");
-
+    /// Check that the project's globs found at least one input, listing the
+    /// globs if they didn't, since that's usually a typo in one of them.
+    fn check_inputs_found(&self) -> Result<(), SyncError> {
+        if !self.inputs.is_empty() {
             return Ok(());
         }
 
-        log::trace!("Packing images...");
-        let mut packed_images = self.pack_images(&group)?;
+        let globs: Vec<_> = self
+            .configs
+            .iter()
+            .flat_map(|config| {
+                config.inputs.iter().map(move |input| {
+                    format!("{} (from {})", input.glob, config.file_path.display())
+                })
+            })
+            .collect();
 
-        log::trace!("Alpha-bleeding {} packed images...", packed_images.len());
+        Err(SyncError::NoInputs {
+            globs: globs.join(", "),
+        })
+    }
 
-        for (i, packed_image) in packed_images.iter_mut().enumerate() {
-            log::trace!("Bleeding image {}", i);
+    /// Report inputs that were matched by a glob, but that Tarmac doesn't know
+    /// how to sync. These usually come from globs that are broader than they
+    /// were meant to be, so we summarize them instead of listing every file.
+    fn check_unrecognized_inputs(&mut self) {
+        const MAX_EXAMPLES: usize = 3;
+
+        let unrecognized: Vec<_> = self
+            .inputs
+            .iter()
+            .filter(|(_, input)| !self.root_config().is_image_path(&input.path))
+            .map(|(name, _)| name.to_string())
+            .collect();
 
-            alpha_bleed(&mut packed_image.image);
+        if unrecognized.is_empty() {
+            return;
         }
 
-        log::trace!("Syncing packed images...");
-        for packed_image in &packed_images {
-            self.sync_packed_image(backend, packed_image)?;
+        let mut examples = unrecognized[..unrecognized.len().min(MAX_EXAMPLES)].join(", ");
+        if unrecognized.len() > MAX_EXAMPLES {
+            examples.push_str(&format!(", and {} more", unrecognized.len() - MAX_EXAMPLES));
         }
 
-        Ok(())
+        let error = SyncError::UnrecognizedInputs {
+            count: unrecognized.len(),
+            examples,
+        };
+
+        match self.root_config().unrecognized_inputs {
+            Severity::Warn => log::warn!("{}", error),
+            Severity::Error => self.raise_error(error),
+        }
     }
 
-    fn are_inputs_unchanged(&self, group: &[AssetName]) -> bool {
-        for name in group {
-            if let Some(manifest) = self.original_manifest.inputs.get(name) {
-                let input = &self.inputs[name];
-                let unchanged = input.is_unchanged_since_last_sync(manifest);
+    /// Check that each image's contents are in the format that its file
+    /// extension says they are, like a JPEG that was renamed to `.png`.
+    /// Otherwise, these images fail to decode with confusing errors.
+    fn check_image_formats(&mut self) {
+        let mut mismatches = Vec::new();
 
-                if !unchanged {
-                    log::trace!("Input {} changed since last sync", name);
+        for input in self.inputs.values() {
+            let expected = match FileFormat::from_extension(&input.path) {
+                Some(format) => format,
+                None => continue,
+            };
 
-                    return false;
+            // Contents that aren't any image format we know will fail to
+            // decode or upload later with a better error.
+            if let Some(actual) = FileFormat::sniff(&input.contents) {
+                if actual != expected {
+                    mismatches.push(SyncError::FormatMismatch {
+                        path: input.path.clone(),
+                        expected,
+                        actual,
+                    });
                 }
-            } else {
-                log::trace!(
-                    "Input {} was not present last sync, need to re-pack spritesheets",
-                    name
+            }
+        }
+
+        for mismatch in mismatches {
+            match self.root_config().format_mismatch {
+                Severity::Warn => log::warn!("{}", mismatch),
+                Severity::Error => self.raise_error(mismatch),
+            }
+        }
+    }
+
+    fn input_kind(&self, input: &SyncInput) -> InputKind {
+        InputKind::new(input, &self.configs[input.config_index], self.root_config())
+    }
+
+    /// Groups together all of the image inputs that can be processed together,
+    /// like inputs that can be packed into the same spritesheets.
+    fn group_inputs_by_kind(&self) -> BTreeMap<InputKind, Vec<AssetName>> {
+        let mut compatible_input_groups = BTreeMap::new();
+
+        for (input_name, input) in &self.inputs {
+            // Unrecognized inputs are reported once after discovery instead.
+            if !self.root_config().is_image_path(&input.path) {
+                continue;
+            }
+
+            let kind = self.input_kind(input);
+
+            let input_group = compatible_input_groups.entry(kind).or_insert_with(Vec::new);
+
+            input_group.push(input_name.clone());
+        }
+
+        compatible_input_groups
+    }
+
+    fn sync_with_backend<S: SyncBackend>(&mut self, backend: &mut S) {
+        let compatible_input_groups = self.group_inputs_by_kind();
+
+        self.unsynced = compatible_input_groups
+            .values()
+            .flatten()
+            .cloned()
+            .collect();
+
+        'outer: for (kind, group) in compatible_input_groups {
+            if self.check_canceled() {
+                break;
+            }
+
+            // Inputs from configs that weren't selected stay as they were.
+            if group.iter().all(|name| self.frozen.contains(name)) {
+                continue;
+            }
+
+            if kind.packable {
+                if let Err(err) = self.sync_packable_images(backend, kind, group.clone()) {
+                    let rate_limited = err.is_rate_limited();
+
+                    for name in group {
+                        if self.unsynced.contains(&name) {
+                            self.upload_errors.insert(name, describe_error(&err));
+                        }
+                    }
+
+                    if let Some(name) = err.rejected_asset() {
+                        self.rejected_uploads.push(name.to_owned());
+                    }
+
+                    log::debug!("Upload failed (rate limited: {}): {:#?}", rate_limited, err);
+
+                    self.raise_error(err);
+
+                    if rate_limited || self.fail_fast {
+                        self.save_progress();
+                        break 'outer;
+                    }
+                }
+            } else {
+                for input_name in group {
+                    if self.frozen.contains(&input_name) {
+                        continue;
+                    }
+
+                    if self.check_canceled() {
+                        break 'outer;
+                    }
+
+                    match self.sync_unpackable_image(backend, &input_name) {
+                        Ok(()) => {
+                            self.unsynced.remove(&input_name);
+                        }
+                        Err(err) => {
+                            let rate_limited = err.is_rate_limited();
+
+                            self.upload_errors
+                                .insert(input_name.clone(), describe_error(&err));
+
+                            if err.rejected_asset().is_some() {
+                                self.rejected_uploads.push(input_name.to_string());
+                            }
+
+                            self.raise_error(err);
+
+                            if rate_limited || self.fail_fast {
+                                self.save_progress();
+                                break 'outer;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // TODO: Clean up output of inputs that were present in the previous
+        // sync but are no longer present.
+    }
+
+    fn sync_packable_images<S: SyncBackend>(
+        &mut self,
+        backend: &mut S,
+        kind: InputKind,
+        group: Vec<AssetName>,
+    ) -> Result<(), SyncError> {
+        if self.are_inputs_unchanged(&group) {
+            log::info!("Skipping image packing as all inputs are unchanged.");
+
+            for name in &group {
+                self.unsynced.remove(name);
+            }
+
+            return Ok(());
+        }
+
+        let mut packed_images = match self.update_spritesheets_in_place(&kind, &group)? {
+            Some(packed_images) => {
+                // Spritesheets without any changed images keep their assets.
+                let updated: HashSet<&AssetName> = packed_images
+                    .iter()
+                    .flat_map(|packed_image| packed_image.slices.keys())
+                    .collect();
+
+                for name in &group {
+                    if !updated.contains(name) {
+                        self.unsynced.remove(name);
+                    }
+                }
+
+                packed_images
+            }
+            None => {
+                log::trace!("Packing images...");
+                self.pack_images(&kind, &group)?
+            }
+        };
+
+        log::trace!("Alpha-bleeding {} packed images...", packed_images.len());
+
+        let bleed_times = self.bleed_spritesheets(&mut packed_images)?;
+        for (i, elapsed) in bleed_times.into_iter().enumerate() {
+            self.timings.borrow_mut().record(
+                format!("spritesheet {}", i + 1),
+                Stage::Bleed,
+                elapsed,
+            );
+        }
+
+        for packed_image in &packed_images {
+            self.dump_spritesheet(packed_image)?;
+            self.dump_layout_svg(packed_image)?;
+        }
+
+        log::trace!("Syncing packed images...");
+        for packed_image in &packed_images {
+            // Spritesheets that aren't uploaded keep their inputs unsynced, so
+            // the manifest keeps what it knew about them.
+            if self.check_canceled() {
+                break;
+            }
+
+            self.sync_packed_image(backend, packed_image)?;
+        }
+
+        Ok(())
+    }
+
+    /// Packs all packable inputs into spritesheets the same way a sync would,
+    /// but only reports the resulting layout instead of uploading anything.
+    pub(super) fn preview_spritesheets(&self) -> Result<Vec<SpritesheetPreview>, SyncError> {
+        let mut previews = Vec::new();
+
+        for (kind, group) in self.group_inputs_by_kind() {
+            if !kind.packable {
+                continue;
+            }
+
+            for packed_image in self.pack_images(&kind, &group)? {
+                previews.push(SpritesheetPreview {
+                    dpi_scale: kind.dpi_scale,
+                    atlas_group: kind.atlas_group.clone(),
+                    size: packed_image.image.size(),
+                    sprites: packed_image.slices.into_iter().collect(),
+                    packing: packed_image.packing,
+                });
+            }
+        }
+
+        Ok(previews)
+    }
+
+    /// Decodes every image input, sorted by name. Images that can't be decoded
+    /// are skipped with a warning.
+    pub(super) fn decode_images(&self) -> Vec<(AssetName, Image, ResizeFilter)> {
+        let mut images = Vec::new();
+
+        for (name, input) in &self.inputs {
+            if !self.root_config().is_image_path(&input.path) {
+                continue;
+            }
+
+            match decode_input_image(&input.path, &input.contents) {
+                Ok(image) => images.push((name.clone(), image, input.config.resize_filter)),
+                Err(err) => log::warn!("Skipping {}: {}", name, err),
+            }
+        }
+
+        images
+    }
+
+    /// Makes the same decisions about what to upload that a sync would, but
+    /// only counts the uploads instead of making them.
+    pub(super) fn plan_uploads(&self) -> Result<UploadPlan, SyncError> {
+        let mut plan = UploadPlan::default();
+        let mut uploaded_hashes: HashSet<String> = self.uploaded_by_hash.keys().cloned().collect();
+
+        for (kind, group) in self.group_inputs_by_kind() {
+            if kind.packable {
+                if self.are_inputs_unchanged(&group) {
+                    continue;
+                }
+
+                let mut packed_images = match self.update_spritesheets_in_place(&kind, &group)? {
+                    Some(packed_images) => packed_images,
+                    None => self.pack_images(&kind, &group)?,
+                };
+
+                // Spritesheets are hashed after alpha bleeding, just like when
+                // syncing.
+                self.bleed_spritesheets(&mut packed_images)?;
+
+                for packed_image in packed_images {
+                    if self.root_config().hash_spritesheet_pixels {
+                        let pixel_hash = spritesheet_pixel_hash(&packed_image);
+
+                        if self.spritesheet_ids.contains_key(&pixel_hash) {
+                            plan.reused_spritesheets += 1;
+                            continue;
+                        }
+                    }
+
+                    let mut encoded_image = Vec::new();
+                    packed_image.image.encode_png_with_options(
+                        &mut encoded_image,
+                        self.root_config().spritesheet_png_compression,
+                        self.root_config().spritesheet_png_optimize_colors,
+                    )?;
+
+                    if !uploaded_hashes.insert(generate_asset_hash(&encoded_image)) {
+                        plan.reused_spritesheets += 1;
+                        continue;
+                    }
+
+                    let previously_uploaded = packed_image
+                        .slices
+                        .keys()
+                        .any(|name| self.inputs[name].id.is_some());
+
+                    if previously_uploaded {
+                        plan.changed_spritesheets += 1;
+                    } else {
+                        plan.new_spritesheets += 1;
+                    }
+                }
+            } else {
+                for name in &group {
+                    let status = self.input_status(name);
+
+                    if status.is_unchanged() {
+                        continue;
+                    }
+
+                    if !uploaded_hashes.insert(self.inputs[name].hash.clone()) {
+                        plan.duplicate_images += 1;
+                    } else if status.is_new() {
+                        plan.new_images += 1;
+                    } else {
+                        plan.changed_images += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    fn are_inputs_unchanged(&self, group: &[AssetName]) -> bool {
+        for name in group {
+            let status = self.input_status(name);
+
+            if !status.is_unchanged() {
+                log::trace!(
+                    "Input {} needs to be synced because {}, need to re-pack spritesheets",
+                    name,
+                    status
                 );
 
                 return false;
@@ -398,180 +1406,950 @@ impl SyncSession {
         true
     }
 
-    fn pack_images(&self, group: &[AssetName]) -> Result<Vec<PackedImage>, SyncError> {
+    /// Tells whether the given input has changed since the last sync.
+    fn input_status(&self, name: &AssetName) -> InputStatus {
+        let input = &self.inputs[name];
+        input.status_since_last_sync(self.original_manifest.inputs.get(name))
+    }
+
+    /// Finds the input that originated from the given path, if there is one.
+    pub(super) fn find_input_by_path(&self, path: &Path) -> Option<&AssetName> {
+        let path = fs::canonicalize(path).ok()?;
+
+        self.inputs
+            .iter()
+            .find(|(_, input)| fs::canonicalize(&input.path).ok().as_ref() == Some(&path))
+            .map(|(name, _)| name)
+    }
+
+    pub(super) fn is_input_packable(&self, name: &AssetName) -> bool {
+        self.inputs[name].config.packable
+    }
+
+    /// Records that the given input was already uploaded as the asset with the
+    /// given ID, so that the next sync sees it as unchanged. The asset is
+    /// downloaded first to make sure that it exists.
+    ///
+    /// Only this input's entry in the manifest changes. Everything else keeps
+    /// what it had from the last sync.
+    pub(super) fn adopt_input(
+        &mut self,
+        name: &AssetName,
+        id: u64,
+        api_client: &mut RobloxApiClient,
+    ) -> Result<(), SyncError> {
+        log::debug!("Downloading asset ID {} to check that it exists", id);
+        api_client.download_image(id)?;
+
+        let input = &self.inputs[name];
+
+        let mut manifest = self.original_manifest.clone();
+        let previous = manifest.inputs.insert(
+            name.clone(),
+            InputManifest {
+                hash: input.hash.clone(),
+                id: Some(id),
+                slice: None,
+                packable: input.config.packable,
+                trim: input.config.trim,
+                trim_info: None,
+                allow_tiling: input.config.allow_tiling,
+                tiles: Vec::new(),
+                partial_tiles: BTreeMap::new(),
+                atlas_group: input.config.atlas_group.clone(),
+                padding: input.config.padding,
+                last_error: None,
+                tags: input.config.tags.clone(),
+            },
+        );
+
+        if let Some(previous_id) = previous.and_then(|previous| previous.id) {
+            log::info!("{} previously referred to asset ID {}", name, previous_id);
+        }
+
+        manifest.write_to_file(self.root_config().manifest_file_path())?;
+        self.original_manifest = manifest;
+
+        Ok(())
+    }
+
+    /// Describes whether the given input will be uploaded by the next sync,
+    /// and why.
+    pub(super) fn explain_input(&self, name: &AssetName) -> String {
+        let input = &self.inputs[name];
+
+        if !self.root_config().is_image_path(&input.path) {
+            return format!("{} is not an image, so Tarmac will not upload it.", name);
+        }
+
+        let status = self.input_status(name);
+
+        if !status.is_unchanged() {
+            let last_error = self
+                .original_manifest
+                .inputs
+                .get(name)
+                .and_then(|original| original.last_error.as_ref());
+
+            return match last_error {
+                Some(error) => format!(
+                    "{} will be uploaded because {}. Its last upload failed: {}",
+                    name, status, error
+                ),
+                None => format!("{} will be uploaded because {}.", name, status),
+            };
+        }
+
+        if !input.config.packable {
+            return format!("{} will not be uploaded because {}.", name, status);
+        }
+
+        // Packed images are uploaded together, so an unchanged image still
+        // needs to be uploaded if anything else it's packed with has changed.
+        let kind = self.input_kind(input);
+        let changed_neighbor = self
+            .group_inputs_by_kind()
+            .remove(&kind)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|other| {
+                let status = self.input_status(&other);
+                (other, status)
+            })
+            .find(|(_, status)| !status.is_unchanged());
+
+        match changed_neighbor {
+            Some((other, other_status)) => format!(
+                "{} is unchanged, but will be re-packed and uploaded because it shares \
+                 spritesheets with {}, and {}.",
+                name, other, other_status
+            ),
+            None => format!("{} will not be uploaded because {}.", name, status),
+        }
+    }
+
+    /// Alpha bleeds every given spritesheet, returning how long each one took.
+    ///
+    /// Spritesheets don't depend on each other, so several are bled at once.
+    /// Each spritesheet is still bled by a single worker.
+    fn bleed_spritesheets(
+        &self,
+        packed_images: &mut [PackedImage],
+    ) -> Result<Vec<Duration>, SyncError> {
+        let linear_blending = self.root_config().linear_blending;
+
+        // Zero threads lets rayon start one for each CPU.
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.bleed_jobs.unwrap_or(0))
+            .build()?;
+
+        let bleed_times = pool.install(|| {
+            packed_images
+                .par_iter_mut()
+                .enumerate()
+                .map(|(i, packed_image)| {
+                    log::trace!("Bleeding image {}", i);
+
+                    let start = Instant::now();
+                    alpha_bleed(&mut packed_image.image, linear_blending);
+                    start.elapsed()
+                })
+                .collect()
+        });
+
+        Ok(bleed_times)
+    }
+
+    /// Packs a group of inputs that all share the given kind into as few
+    /// spritesheets as possible.
+    fn pack_images(
+        &self,
+        kind: &InputKind,
+        group: &[AssetName],
+    ) -> Result<Vec<PackedImage>, SyncError> {
+        log::trace!("Packing {} inputs of kind {:?}", group.len(), kind);
+
         let mut packos_inputs = Vec::new();
         let mut images_by_id = HashMap::new();
 
         for name in group {
-            let input = &self.inputs[&name];
-            let image = Image::decode_png(input.contents.as_slice())?;
+            let (image, trim_info) = self.decode_packable_input(name)?;
+
+            let mut input = InputItem::new(image.size());
+            if let Some(padding) = self.inputs[name].config.padding {
+                input = input.with_padding(padding);
+            }
+
+            images_by_id.insert(input.id(), (name, image, trim_info));
+            packos_inputs.push(input);
+        }
+
+        let mut packer = SimplePacker::new()
+            .min_size(kind.min_spritesheet_size)
+            .max_size(kind.max_spritesheet_size)
+            .padding(1)
+            .collect_diagnostics(true);
+
+        if let Some(max_items) = self.root_config().max_spritesheet_items {
+            packer = packer.max_items_per_bucket(max_items);
+        }
+
+        let start = Instant::now();
+        let pack_results = packer.pack_vec(packos_inputs).map_err(|err| match err {
+            PackError::ItemDoesNotFit { id, size, max_size } => SyncError::ImageTooLarge {
+                name: images_by_id[&id].0.clone(),
+                size,
+                max_size,
+            },
+            PackError::MinSizeLargerThanMaxSize { min_size, max_size } => {
+                SyncError::MinSpritesheetSizeTooLarge { min_size, max_size }
+            }
+            source => SyncError::Pack { source },
+        })?;
+        self.timings.borrow_mut().record(
+            format!("{} images", group.len()),
+            Stage::Pack,
+            start.elapsed(),
+        );
+
+        let spritesheet_count = pack_results.buckets().len();
+        let occupancy = pack_results.occupancy();
+
+        let name_of = |id: packos::Id| images_by_id[&id].0.clone();
+
+        if spritesheet_count >= MANY_SPRITESHEETS && occupancy < LOW_SPRITESHEET_OCCUPANCY {
+            // The images that spilled over into new spritesheets are usually
+            // the ones that need a closer look.
+            let overflowed: Vec<String> = pack_results
+                .buckets()
+                .iter()
+                .filter_map(|bucket| bucket.diagnostics()?.overflowed_with())
+                .take(3)
+                .map(|id| name_of(id).to_string())
+                .collect();
+
+            log::warn!(
+                "{} images were packed into {} spritesheets that are only {:.0}% full, \
+                 which means more uploads than necessary. Check that max-spritesheet-size \
+                 ({}x{}) and max-spritesheet-items suit these images. Spritesheets filled \
+                 up when these images didn't fit: {}",
+                group.len(),
+                spritesheet_count,
+                occupancy * 100.0,
+                kind.max_spritesheet_size.0,
+                kind.max_spritesheet_size.1,
+                overflowed.join(", "),
+            );
+        }
+
+        let mut packed_images = Vec::new();
+
+        let (r, g, b, a) = kind.spritesheet_background;
+        let background = Pixel::new(r, g, b, a);
+
+        for bucket in pack_results.buckets_sorted() {
+            let mut image = Image::new_filled_rgba8(bucket.size(), background);
+            let mut slices: HashMap<AssetName, _> = HashMap::new();
+            let mut trim_infos = HashMap::new();
+
+            for item in bucket.items() {
+                let (name, sprite_image, trim_info) = &images_by_id[&item.id()];
+
+                image.blit(sprite_image, item.position());
+
+                slices.insert((*name).clone(), ImageSlice::from(item));
+
+                if let Some(trim_info) = trim_info {
+                    trim_infos.insert((*name).clone(), *trim_info);
+                }
+            }
+
+            let packing = bucket.diagnostics().map(|diagnostics| PackingDetails {
+                resizes: diagnostics.resizes(),
+                resized_for: diagnostics.resized_for().map(name_of),
+                overflowed_with: diagnostics.overflowed_with().map(name_of),
+            });
+
+            packed_images.push(PackedImage {
+                image,
+                slices,
+                trim_infos,
+                packing,
+            });
+        }
+
+        Ok(packed_images)
+    }
+
+    /// Decodes a packable input, trimming it if its config asks for that.
+    fn decode_packable_input(
+        &self,
+        name: &AssetName,
+    ) -> Result<(Image, Option<TrimInfo>), SyncError> {
+        let input = &self.inputs[name];
+
+        let start = Instant::now();
+        let mut image = decode_input_image(&input.path, &input.contents)?;
+        self.timings
+            .borrow_mut()
+            .record(name.to_string(), Stage::Decode, start.elapsed());
+
+        let mut trim_info = None;
+
+        if input.config.trim {
+            // Images that are entirely transparent are left alone, since
+            // there's nothing sensible to trim them down to.
+            if let Some((offset, size)) = image.opaque_bounds() {
+                if size != image.size() {
+                    trim_info = Some(TrimInfo {
+                        original_size: image.size(),
+                        offset,
+                    });
+                    image = image
+                        .crop(offset, size)
+                        .expect("opaque bounds are always inside the image");
+                }
+            }
+        }
+
+        Ok((image, trim_info))
+    }
+
+    /// Rebuilds only the spritesheets that have changed images in them, with
+    /// every image kept in the same place as the last sync, if the project has
+    /// `update-spritesheets-in-place` enabled.
+    ///
+    /// Spritesheets are rebuilt from the inputs on disk instead of downloading
+    /// the old spritesheet, which gives the same pixels without depending on
+    /// what Roblox did to the uploaded image.
+    ///
+    /// Returns `None` when the group has to be packed from scratch instead,
+    /// like when an image was added or changed size.
+    fn update_spritesheets_in_place(
+        &self,
+        kind: &InputKind,
+        group: &[AssetName],
+    ) -> Result<Option<Vec<PackedImage>>, SyncError> {
+        if !self.root_config().update_spritesheets_in_place {
+            return Ok(None);
+        }
+
+        let mut spritesheets: BTreeMap<u64, Vec<(&AssetName, ImageSlice)>> = BTreeMap::new();
+
+        for name in group {
+            let input = &self.inputs[name];
+            let status = self.input_status(name);
+
+            // A changed config can mean a different trim or a different
+            // spritesheet, so only changed contents can be updated in place.
+            match (input.id, input.slice, status) {
+                (Some(id), Some(slice), InputStatus::Unchanged | InputStatus::ContentsChanged) => {
+                    spritesheets.entry(id).or_default().push((name, slice));
+                }
+                _ => {
+                    // New inputs always need packing, so they aren't worth
+                    // calling out.
+                    let level = if status.is_new() {
+                        log::Level::Debug
+                    } else {
+                        log::Level::Info
+                    };
+
+                    log::log!(
+                        level,
+                        "Packing spritesheets from scratch because {} can't be updated in place: {}",
+                        name,
+                        status
+                    );
+                    return Ok(None);
+                }
+            }
+        }
+
+        let (r, g, b, a) = kind.spritesheet_background;
+        let background = Pixel::new(r, g, b, a);
+
+        let mut packed_images = Vec::new();
+
+        for (id, inputs) in spritesheets {
+            let changed = inputs
+                .iter()
+                .filter(|(name, _)| !self.input_status(name).is_unchanged())
+                .count();
+
+            if changed == 0 {
+                continue;
+            }
+
+            // Identical spritesheets from different groups share an asset ID,
+            // so their images can claim the same space.
+            for (i, (name, slice)) in inputs.iter().enumerate() {
+                if let Some((other, _)) = inputs[i + 1..]
+                    .iter()
+                    .find(|(_, other_slice)| slices_overlap(slice, other_slice))
+                {
+                    log::info!(
+                        "Packing spritesheets from scratch because {} and {} overlap in asset ID {}",
+                        name,
+                        other,
+                        id
+                    );
+                    return Ok(None);
+                }
+            }
+
+            let size = inputs
+                .iter()
+                .fold(kind.min_spritesheet_size, |size, (_, slice)| {
+                    (size.0.max(slice.max().0), size.1.max(slice.max().1))
+                });
+
+            let mut image = Image::new_filled_rgba8(size, background);
+            let mut slices = HashMap::new();
+            let mut trim_infos = HashMap::new();
+
+            for (name, slice) in inputs {
+                let (sprite, trim_info) = self.decode_packable_input(name)?;
+
+                if sprite.size() != slice.size() {
+                    log::info!(
+                        "Packing spritesheets from scratch because {} changed size from {}x{} to {}x{}",
+                        name,
+                        slice.size().0,
+                        slice.size().1,
+                        sprite.size().0,
+                        sprite.size().1,
+                    );
+                    return Ok(None);
+                }
+
+                image.blit(&sprite, slice.min());
+                slices.insert(name.clone(), slice);
+
+                if let Some(trim_info) = trim_info {
+                    trim_infos.insert(name.clone(), trim_info);
+                }
+            }
+
+            log::info!(
+                "Updating {} changed image(s) in place in the spritesheet with asset ID {}",
+                changed,
+                id
+            );
+
+            packed_images.push(PackedImage {
+                image,
+                slices,
+                trim_infos,
+                packing: None,
+            });
+        }
+
+        Ok(Some(packed_images))
+    }
+
+    /// Writes a copy of the given spritesheet with each of its images outlined,
+    /// if `--dump-spritesheets` was passed.
+    fn dump_spritesheet(&mut self, packed_image: &PackedImage) -> Result<(), SyncError> {
+        let dump = match &mut self.spritesheet_dump {
+            Some(dump) => dump,
+            None => return Ok(()),
+        };
+
+        let mut image = packed_image.image.clone();
+        for slice in packed_image.slices.values() {
+            image.draw_rect_outline((slice.min(), slice.size()), dump.outline_color);
+        }
+
+        dump.count += 1;
+        let path = dump.folder.join(format!("spritesheet-{}.png", dump.count));
+        log::info!("Writing spritesheet to {}", path.display());
+
+        fs_err::create_dir_all(&dump.folder)?;
+        let file = BufWriter::new(fs_err::File::create(&path)?);
+        // Dumps are only for looking at, so there's no point spending time
+        // making them small.
+        image.encode_png_with_compression(file, PngCompression::Fast)?;
+
+        Ok(())
+    }
+
+    /// Writes an SVG of the given spritesheet's layout, if `--layout-svg` was
+    /// passed.
+    fn dump_layout_svg(&mut self, packed_image: &PackedImage) -> Result<(), SyncError> {
+        let dump = match &mut self.layout_svg_dump {
+            Some(dump) => dump,
+            None => return Ok(()),
+        };
+
+        let svg = layout_svg(packed_image.image.size(), &packed_image.slices);
+
+        dump.count += 1;
+        let path = dump.folder.join(format!("spritesheet-{}.svg", dump.count));
+        log::info!("Writing spritesheet layout to {}", path.display());
+
+        fs_err::create_dir_all(&dump.folder)?;
+        fs_err::write(&path, svg)?;
+
+        Ok(())
+    }
+
+    fn sync_packed_image<S: SyncBackend>(
+        &mut self,
+        backend: &mut S,
+        packed_image: &PackedImage,
+    ) -> Result<(), SyncError> {
+        let mut encoded_image = Vec::new();
+        packed_image.image.encode_png_with_options(
+            &mut encoded_image,
+            self.root_config().spritesheet_png_compression,
+            self.root_config().spritesheet_png_optimize_colors,
+        )?;
+
+        let hash = generate_asset_hash(&encoded_image);
+
+        let pixel_hash = if self.root_config().hash_spritesheet_pixels {
+            Some(spritesheet_pixel_hash(packed_image))
+        } else {
+            None
+        };
+
+        let existing_id = pixel_hash
+            .as_ref()
+            .and_then(|pixel_hash| self.spritesheet_ids.get(pixel_hash));
+
+        let response = match existing_id {
+            Some(&id) => {
+                log::info!(
+                    "Spritesheet has the same pixels as asset ID {}, reusing it",
+                    id
+                );
+
+                UploadResponse {
+                    id,
+                    moderation_state: None,
+                }
+            }
+            None => {
+                let upload_data = UploadInfo {
+                    name: "spritesheet".to_owned(),
+                    contents: encoded_image,
+                    hash: hash.clone(),
+                    preview: None,
+                };
+
+                self.upload_deduplicated(backend, upload_data)?
+            }
+        };
+        let id = response.id;
+
+        if let Some(pixel_hash) = pixel_hash {
+            self.spritesheet_ids.insert(pixel_hash, id);
+        }
+
+        // Identical spritesheets from other groups, even ones packed in a
+        // later sync, can share this asset.
+        self.spritesheet_hashes.insert(hash, id);
+
+        // Apply resolved metadata back to the inputs
+        for (asset_name, slice) in &packed_image.slices {
+            let input = self.inputs.get_mut(asset_name).unwrap();
+
+            input.id = Some(id);
+            input.slice = Some(*slice);
+            input.trim_info = packed_image.trim_infos.get(asset_name).copied();
+
+            self.unsynced.remove(asset_name);
+        }
+
+        self.record_upload("spritesheet", &response)
+    }
+
+    fn sync_unpackable_image<S: SyncBackend>(
+        &mut self,
+        backend: &mut S,
+        input_name: &AssetName,
+    ) -> Result<(), SyncError> {
+        let input = &self.inputs[input_name];
 
-            let input = InputItem::new(image.size());
+        let status = input.status_since_last_sync(self.original_manifest.inputs.get(input_name));
 
-            images_by_id.insert(input.id(), (name, image));
-            packos_inputs.push(input);
+        // TODO: If only the config has changed, we might not need to reupload
+        // this image?
+        if status.is_unchanged() {
+            log::trace!("Input is unchanged.");
+            return Ok(());
         }
 
-        let packer = SimplePacker::new()
-            .max_size(self.root_config().max_spritesheet_size)
-            .padding(1);
-
-        let pack_results = packer.pack(packos_inputs);
-        let mut packed_images = Vec::new();
+        log::trace!("Uploading because {}...", status);
 
-        for bucket in pack_results.buckets() {
-            let mut image = Image::new_empty_rgba8(bucket.size());
-            let mut slices: HashMap<AssetName, _> = HashMap::new();
+        if input.config.allow_tiling {
+            let max_size = self.input_kind(input).max_spritesheet_size;
+            let image = decode_input_image(&input.path, &input.contents)?;
+            let (width, height) = image.size();
 
-            for item in bucket.items() {
-                let (name, sprite_image) = &images_by_id[&item.id()];
+            if width > max_size.0 || height > max_size.1 {
+                return self.sync_tiled_image(backend, input_name, &image, max_size);
+            }
+        }
 
-                image.blit(sprite_image, item.position());
+        let upload_data = UploadInfo {
+            name: input.human_name(),
+            contents: input.contents.clone(),
+            hash: input.hash.clone(),
+            preview: input.preview(),
+        };
 
-                let slice = ImageSlice::new(item.position(), item.max());
-                slices.insert((*name).clone(), slice);
-            }
+        let name = upload_data.name.clone();
+        let response = self.upload_deduplicated(backend, upload_data)?;
 
-            packed_images.push(PackedImage { image, slices });
-        }
+        let input = self.inputs.get_mut(input_name).unwrap();
+        input.id = Some(response.id);
+        input.tiles = Vec::new();
 
-        Ok(packed_images)
+        self.record_upload(&name, &response)
     }
 
-    fn sync_packed_image<S: SyncBackend>(
+    /// Uploads an image that's too large to be uploaded whole as a grid of
+    /// tiles, each no larger than `max_size`.
+    fn sync_tiled_image<S: SyncBackend>(
         &mut self,
         backend: &mut S,
-        packed_image: &PackedImage,
+        input_name: &AssetName,
+        image: &Image,
+        max_size: (u32, u32),
     ) -> Result<(), SyncError> {
-        let mut encoded_image = Vec::new();
-        packed_image.image.encode_png(&mut encoded_image)?;
+        let input = &self.inputs[input_name];
+        let human_name = input.human_name();
+        let preview = input.preview();
+
+        let slices = tile_slices(image.size(), max_size);
+        log::info!("Splitting {} into {} tiles", input_name, slices.len());
+
+        let mut tiles = Vec::with_capacity(slices.len());
+
+        for (index, slice) in slices.into_iter().enumerate() {
+            let tile_image = image
+                .crop(slice.min(), slice.size())
+                .expect("tiles should always be inside of their image");
+
+            let mut encoded_image = Vec::new();
+            tile_image.encode_png_with_options(
+                &mut encoded_image,
+                self.root_config().spritesheet_png_compression,
+                self.root_config().spritesheet_png_optimize_colors,
+            )?;
+
+            let hash = generate_asset_hash(&encoded_image);
+            let upload_data = UploadInfo {
+                name: format!("{} (tile {})", human_name, index + 1),
+                hash: hash.clone(),
+                contents: encoded_image,
+                preview: preview.clone(),
+            };
 
-        let hash = generate_asset_hash(&encoded_image);
+            let name = upload_data.name.clone();
+            let response = self.upload_deduplicated(backend, upload_data)?;
+
+            // Recorded before anything else can fail, so that the tile isn't
+            // uploaded again if a later tile fails.
+            self.partial_tiles
+                .entry(input_name.clone())
+                .or_default()
+                .insert(hash, response.id);
+            self.record_upload(&name, &response)?;
+
+            tiles.push(ImageTile {
+                id: response.id,
+                slice,
+            });
+        }
 
-        let upload_data = UploadInfo {
-            name: "spritesheet".to_owned(),
-            contents: encoded_image,
-            hash: hash.clone(),
-        };
+        // The input only counts as uploaded once every one of its tiles is.
+        self.partial_tiles.remove(input_name);
+        let input = self.inputs.get_mut(input_name).unwrap();
+        input.id = tiles.first().map(|tile| tile.id);
+        input.tiles = tiles;
+
+        Ok(())
+    }
 
-        let id = backend.upload(upload_data)?.id;
+    /// Keeps track of a successful upload, saving our progress to the manifest
+    /// every so often so that an interrupted sync doesn't forget about it.
+    fn record_upload(&mut self, name: &str, response: &UploadResponse) -> Result<(), SyncError> {
+        if !response.is_approved() {
+            let state = response.moderation_state.as_deref().unwrap_or_default();
+            log::debug!(
+                "{} was uploaded to ID {}, but is not approved by moderation yet ({})",
+                name,
+                response.id,
+                state
+            );
 
-        // Apply resolved metadata back to the inputs
-        for (asset_name, slice) in &packed_image.slices {
-            let input = self.inputs.get_mut(asset_name).unwrap();
+            self.unapproved_uploads
+                .push(format!("{} (ID {}, {})", name, response.id, state));
+        }
 
-            input.id = Some(id);
-            input.slice = Some(*slice);
+        self.uploads_since_manifest_write += 1;
+
+        if self.uploads_since_manifest_write >= MANIFEST_WRITE_INTERVAL {
+            self.write_manifest()?;
         }
 
         Ok(())
     }
 
-    fn sync_unpackable_image<S: SyncBackend>(
+    /// Uploads the given data, unless something with the same contents has
+    /// already been uploaded during this sync.
+    fn upload_deduplicated<S: SyncBackend>(
         &mut self,
         backend: &mut S,
-        input_name: &AssetName,
-    ) -> Result<(), SyncError> {
-        let input = self.inputs.get_mut(input_name).unwrap();
+        upload_data: UploadInfo,
+    ) -> Result<UploadResponse, SyncError> {
+        if let Some(&id) = self.uploaded_by_hash.get(&upload_data.hash) {
+            log::info!(
+                "{} has the same contents as asset ID {}, reusing it",
+                upload_data.name,
+                id
+            );
 
-        let upload_data = UploadInfo {
-            name: input.human_name(),
-            contents: input.contents.clone(),
-            hash: input.hash.clone(),
-        };
+            // If the original upload wasn't approved, it was already reported.
+            return Ok(UploadResponse {
+                id,
+                moderation_state: None,
+            });
+        }
 
-        let id = if let Some(input_manifest) = self.original_manifest.inputs.get(&input_name) {
-            // This input existed during our last sync operation. We'll compare
-            // the current state with the previous one to see if we need to take
-            // action.
+        let hash = upload_data.hash.clone();
+        let name = upload_data.name.clone();
 
-            if input_manifest.hash != input.hash {
-                // The file's contents have been edited since the last sync.
+        let start = Instant::now();
+        let response = backend.upload(upload_data)?;
+        self.timings
+            .borrow_mut()
+            .record(name, Stage::Upload, start.elapsed());
 
-                log::trace!("Contents changed...");
+        self.upload_count += 1;
 
-                backend.upload(upload_data)?.id
-            } else if input.id.is_some() {
-                // The file's contents are the same as the previous sync and
-                // this image has been uploaded previously.
+        self.uploaded_by_hash.insert(hash, response.id);
 
-                if input_manifest.packable != input.config.packable {
-                    // Only the file's config has changed.
-                    //
-                    // TODO: We might not need to reupload this image?
+        Ok(response)
+    }
 
-                    log::trace!("Config changed...");
+    /// Counts the inputs whose assets are different from the last sync, like
+    /// new inputs and packed images that moved to a new spritesheet.
+    fn changed_input_count(&self) -> usize {
+        let slice_bounds = |slice: &ImageSlice| (slice.min(), slice.max());
 
-                    backend.upload(upload_data)?.id
-                } else {
-                    // Nothing has changed, we're good to go!
+        self.inputs
+            .iter()
+            .filter(|(_, input)| input.asset_ids().next().is_some())
+            .filter(
+                |(name, input)| match self.original_manifest.inputs.get(name) {
+                    Some(original) => {
+                        let original_ids: Vec<u64> = original
+                            .id
+                            .into_iter()
+                            .chain(original.tiles.iter().map(|tile| tile.id))
+                            .collect();
+
+                        input.asset_ids().collect::<Vec<_>>() != original_ids
+                            || input.slice.as_ref().map(slice_bounds)
+                                != original.slice.as_ref().map(slice_bounds)
+                    }
+                    None => true,
+                },
+            )
+            .count()
+    }
 
-                    log::trace!("Input is unchanged.");
-                    return Ok(());
-                }
-            } else {
-                // This image has never been uploaded, but its hash is present
-                // in the manifest.
+    /// Runs the project's `post-sync-command`, if it has one, telling it about
+    /// what changed through environment variables.
+    fn run_post_sync_command(&self, target: SyncTarget) -> Result<(), SyncError> {
+        let command = match &self.root_config().post_sync_command {
+            Some(command) => command,
+            None => return Ok(()),
+        };
 
-                log::trace!("Image has never been uploaded...");
+        log::info!("Running post-sync command: {}", command);
 
-                backend.upload(upload_data)?.id
-            }
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
         } else {
-            // This input was added since the last sync, if there was one.
-
-            log::trace!("Image was added since last sync...");
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
 
-            backend.upload(upload_data)?.id
+        let target = match target {
+            SyncTarget::Roblox => "roblox",
+            SyncTarget::None => "none",
+            SyncTarget::Debug => "debug",
         };
 
-        input.id = Some(id);
+        let status = shell
+            .arg(command)
+            .current_dir(self.root_config().folder())
+            .env("TARMAC_SYNC_TARGET", target)
+            .env("TARMAC_UPLOADED_ASSETS", self.upload_count.to_string())
+            .env(
+                "TARMAC_CHANGED_INPUTS",
+                self.changed_input_count().to_string(),
+            )
+            .env(
+                "TARMAC_MANIFEST_PATH",
+                self.root_config().manifest_file_path(),
+            )
+            .status()
+            .map_err(|source| SyncError::PostSyncCommandSpawn {
+                command: command.clone(),
+                source,
+            })?;
+
+        if !status.success() {
+            return Err(SyncError::PostSyncCommandFailed {
+                command: command.clone(),
+                status,
+            });
+        }
 
         Ok(())
     }
 
-    fn write_manifest(&self) -> Result<(), SyncError> {
-        log::trace!("Generating new manifest");
+    /// Tells whether the sync was asked to stop with Ctrl-C. The first time
+    /// this notices, it saves the sync's progress so far.
+    fn check_canceled(&mut self) -> bool {
+        if self.canceled {
+            return true;
+        }
+
+        if !cancel::is_requested() {
+            return false;
+        }
+
+        log::warn!("Stopping the sync and saving progress. Press Ctrl-C again to quit right away.");
+
+        self.canceled = true;
+        self.save_progress();
+
+        true
+    }
+
+    /// Saves the progress of a sync that's about to stop early.
+    fn save_progress(&mut self) {
+        if let Err(err) = self.write_manifest() {
+            self.raise_error(err);
+        }
+    }
 
-        let mut manifest = Manifest::default();
+    fn write_manifest(&mut self) -> Result<(), SyncError> {
+        log::trace!("Generating new manifest");
 
-        manifest.inputs = self
+        let inputs: BTreeMap<_, _> = self
             .inputs
             .iter()
-            .map(|(name, input)| {
-                (
+            .filter_map(|(name, input)| {
+                if self.unsynced.contains(name) {
+                    // We haven't uploaded this input's changes yet, so keep
+                    // whatever we knew about it from the last sync, if
+                    // anything.
+                    let original = self.original_manifest.inputs.get(name);
+                    let last_error = self.upload_errors.get(name).cloned();
+                    let partial_tiles = self.partial_tiles.get(name).cloned().unwrap_or_default();
+
+                    if last_error.is_none() && partial_tiles.is_empty() {
+                        return original.map(|original| (name.clone(), original.clone()));
+                    }
+
+                    // Inputs that failed to upload, or that were stopped partway
+                    // through uploading their tiles, are recorded even if
+                    // they're new, so that the failure can be seen and the
+                    // tiles reused. Without an ID, the next sync still uploads
+                    // them.
+                    let entry = match original {
+                        Some(original) => InputManifest {
+                            partial_tiles,
+                            last_error,
+                            ..original.clone()
+                        },
+                        None => InputManifest {
+                            hash: input.hash.clone(),
+                            id: None,
+                            slice: None,
+                            packable: input.config.packable,
+                            trim: input.config.trim,
+                            trim_info: None,
+                            allow_tiling: input.config.allow_tiling,
+                            tiles: Vec::new(),
+                            partial_tiles,
+                            atlas_group: input.config.atlas_group.clone(),
+                            padding: input.config.padding,
+                            last_error,
+                            tags: input.config.tags.clone(),
+                        },
+                    };
+
+                    return Some((name.clone(), entry));
+                }
+
+                Some((
                     name.clone(),
                     InputManifest {
                         hash: input.hash.clone(),
                         id: input.id,
                         slice: input.slice,
                         packable: input.config.packable,
+                        trim: input.config.trim,
+                        trim_info: input.trim_info,
+                        allow_tiling: input.config.allow_tiling,
+                        tiles: input.tiles.clone(),
+                        partial_tiles: BTreeMap::new(),
+                        atlas_group: input.config.atlas_group.clone(),
+                        padding: input.config.padding,
+                        last_error: None,
+                        tags: input.config.tags.clone(),
                     },
-                )
+                ))
             })
             .collect();
 
-        manifest.write_to_folder(self.root_config().folder())?;
+        // Only remember spritesheets that are still used by some input.
+        let known_ids: HashSet<u64> = inputs.values().filter_map(|input| input.id).collect();
+        let still_used = |ids: &BTreeMap<String, u64>| {
+            ids.iter()
+                .filter(|(_, id)| known_ids.contains(id))
+                .map(|(hash, &id)| (hash.clone(), id))
+                .collect()
+        };
+
+        let manifest = Manifest {
+            asset_name_style: self.root_config().asset_name_style,
+            inputs,
+            spritesheets: still_used(&self.spritesheet_ids),
+            spritesheet_hashes: still_used(&self.spritesheet_hashes),
+        };
+        manifest.write_to_file(self.root_config().manifest_file_path())?;
+
+        self.uploads_since_manifest_write = 0;
 
         Ok(())
     }
 
-    fn codegen(&self) -> Result<(), SyncError> {
+    pub(super) fn codegen(&self) -> Result<(), SyncError> {
         log::trace!("Starting codegen");
 
-        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
         struct CodegenCompatibility<'a> {
             output_path: Option<&'a Path>,
         }
 
-        let mut compatible_codegen_groups = HashMap::new();
+        // Sorted so that generated files are always written in the same order.
+        let mut compatible_codegen_groups = BTreeMap::new();
 
         for (input_name, input) in &self.inputs {
-            let output_path = input
-                .config
-                .codegen_path
-                .as_ref()
-                .map(|path| path.as_path());
+            let output_path = input.config.codegen_path.as_deref();
 
             let compat = CodegenCompatibility { output_path };
 
@@ -581,17 +2359,54 @@ impl SyncSession {
             group.push(input_name.clone());
         }
 
+        let mut grouped_module_paths = Vec::new();
+
         for (compat, names) in compatible_codegen_groups {
             let inputs: Vec<_> = names.iter().map(|name| &self.inputs[name]).collect();
             let output_path = compat.output_path;
 
-            perform_codegen(output_path, &inputs)?;
+            perform_codegen(self.root_config(), output_path, &inputs)?;
+            grouped_module_paths.extend(output_path);
+        }
+
+        let root_config = self.root_config();
+        if let Some(index_path) = &root_config.codegen_index_path {
+            log::trace!("Generating codegen index");
+
+            grouped_module_paths.sort();
+            codegen_index(root_config, index_path, &grouped_module_paths)?;
         }
 
         Ok(())
     }
 
-    fn write_asset_list(&self) -> Result<(), SyncError> {
+    /// Warns about inputs that were uploaded but won't be in generated code,
+    /// since nothing can use them by name. Projects that don't generate code at
+    /// all are skipped.
+    pub(super) fn check_codegen_coverage(&self) {
+        if !self.inputs.values().any(|input| input.config.codegen) {
+            return;
+        }
+
+        let inputs: Vec<_> = self.inputs.values().collect();
+        let missing = inputs_missing_from_codegen(&inputs);
+
+        if missing.is_empty() {
+            return;
+        }
+
+        log::warn!(
+            "{} uploaded asset(s) won't be in generated code, because their input groups don't \
+             have codegen enabled or their names are taken by other assets:",
+            missing.len()
+        );
+
+        for input in missing {
+            log::warn!("  {}", input.name);
+        }
+    }
+
+    pub(super) fn write_asset_list(&self) -> Result<(), SyncError> {
         let list_path = match &self.root_config().asset_list_path {
             Some(path) => path,
             None => return Ok(()),
@@ -604,17 +2419,36 @@ impl SyncSession {
 
         let mut file = BufWriter::new(fs_err::File::create(list_path)?);
 
-        let known_ids: BTreeSet<u64> = self.inputs.values().filter_map(|input| input.id).collect();
+        match self.root_config().asset_list_format {
+            AssetListFormat::Plain => {
+                let known_ids: BTreeSet<u64> = self
+                    .inputs
+                    .values()
+                    .flat_map(SyncInput::asset_ids)
+                    .collect();
 
-        for id in known_ids {
-            writeln!(file, "rbxassetid://{}", id)?;
+                for id in known_ids {
+                    writeln!(file, "rbxassetid://{}", id)?;
+                }
+            }
+            AssetListFormat::Csv => {
+                let assets = self
+                    .inputs
+                    .iter()
+                    .flat_map(|(name, input)| input.asset_ids().map(move |id| (name, id)));
+
+                write_asset_csv(&mut file, assets)?;
+            }
         }
 
         file.flush()?;
         Ok(())
     }
 
-    fn populate_asset_cache(&self, api_client: &mut RobloxApiClient) -> Result<(), SyncError> {
+    pub(super) fn populate_asset_cache(
+        &self,
+        api_client: &mut RobloxApiClient,
+    ) -> Result<(), SyncError> {
         let cache_path = match &self.root_config().asset_cache_path {
             Some(path) => path,
             None => return Ok(()),
@@ -622,25 +2456,39 @@ impl SyncSession {
 
         log::debug!("Populating asset cache");
 
-        fs_err::create_dir_all(&cache_path)?;
+        fs_err::create_dir_all(cache_path)?;
+
+        let shared_cache_path = self.root_config().shared_asset_cache_path.as_ref();
+        if let Some(shared_cache_path) = shared_cache_path {
+            fs_err::create_dir_all(shared_cache_path)?;
+        }
 
-        let known_ids: HashSet<u64> = self.inputs.values().filter_map(|input| input.id).collect();
+        let known_ids: HashSet<u64> = self
+            .inputs
+            .values()
+            .flat_map(SyncInput::asset_ids)
+            .collect();
+        let keep = &self.root_config().asset_cache_keep;
 
         // Clean up cache items that aren't present in our current project.
-        for entry in fs_err::read_dir(&cache_path)? {
+        for entry in fs_err::read_dir(cache_path)? {
             let entry = entry?;
             let path = entry.path();
             let metadata = fs_err::metadata(&path)?;
 
-            let name_as_id: Option<u64> = path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .and_then(|name| name.parse().ok());
+            let file_name = entry.file_name();
+
+            let name_as_id: Option<u64> = file_name.to_str().and_then(|name| name.parse().ok());
 
             let should_clean_up;
             if metadata.is_dir() {
-                // Tarmac never generates directories, so we should clean this.
-                should_clean_up = true;
+                // Tarmac never generates directories, so this was put here by
+                // something else. It's not ours to clean up.
+                log::debug!("Leaving directory {} in asset cache", path.display());
+                should_clean_up = false;
+            } else if keep.iter().any(|glob| glob.is_match(&file_name)) {
+                // The project asked us to keep this file around.
+                should_clean_up = false;
             } else if let Some(id) = name_as_id {
                 // This file looks like an ID. If it's not present in this
                 // project, we assume it's from an old sync and clean it up.
@@ -651,34 +2499,39 @@ impl SyncSession {
             }
 
             if should_clean_up {
-                if metadata.is_dir() {
-                    fs_err::remove_dir_all(&path)?;
-                } else {
-                    fs_err::remove_file(&path)?;
-                }
+                log::info!("Removing {} from asset cache", path.display());
+                fs_err::remove_file(&path)?;
             }
         }
 
         for input in self.inputs.values() {
-            if let Some(id) = input.id {
-                let input_path = cache_path.join(format!("{}", id));
+            for id in input.asset_ids() {
+                if is_cached(cache_path, id)? {
+                    continue;
+                }
+
+                let shared_cache_path = match shared_cache_path {
+                    Some(path) => path,
+                    None => {
+                        log::debug!("Downloading asset ID {}", id);
 
-                match fs_err::metadata(&input_path) {
-                    Ok(_) => {
-                        // This asset is already downloaded, we can skip it.
+                        let contents = api_client.download_image(id)?;
+                        write_cached(cache_path, id, &contents)?;
                         continue;
                     }
-                    Err(err) => {
-                        if err.kind() != io::ErrorKind::NotFound {
-                            return Err(err.into());
-                        }
-                    }
-                }
+                };
+
+                // Other projects might be populating the shared cache at the
+                // same time. Writes to it are atomic, so the worst case is two
+                // projects downloading the same asset.
+                if !is_cached(shared_cache_path, id)? {
+                    log::debug!("Downloading asset ID {} to shared asset cache", id);
 
-                log::debug!("Downloading asset ID {}", id);
+                    let contents = api_client.download_image(id)?;
+                    write_cached(shared_cache_path, id, &contents)?;
+                }
 
-                let contents = api_client.download_image(id)?;
-                fs_err::write(input_path, contents)?;
+                link_cached(shared_cache_path, cache_path, id)?;
             }
         }
 
@@ -686,39 +2539,312 @@ impl SyncSession {
     }
 }
 
-fn is_image_asset(path: &Path) -> bool {
-    match path.extension().and_then(|ext| ext.to_str()) {
-        // TODO: Expand the definition of images?
-        Some("png") | Some("jpg") => true,
+/// Tells whether the asset cache at the given path already has a valid image
+/// for the given asset ID. Corrupted images are reported so that they can be
+/// downloaded again.
+fn is_cached(cache_path: &Path, id: u64) -> Result<bool, SyncError> {
+    let path = cache_path.join(id.to_string());
+
+    // Only the start of the file is needed to tell whether it's an image, so
+    // there's no need to read every cached asset in full.
+    let read_header = || -> io::Result<Vec<u8>> {
+        let mut header = Vec::with_capacity(FileFormat::SNIFF_LEN);
+        fs_err::File::open(&path)?
+            .take(FileFormat::SNIFF_LEN as u64)
+            .read_to_end(&mut header)?;
+
+        Ok(header)
+    };
+
+    match read_header() {
+        Ok(header) => {
+            // As long as the asset still looks like an image, it doesn't need
+            // to be downloaded again.
+            if FileFormat::sniff(&header).is_some() {
+                return Ok(true);
+            }
+
+            log::warn!(
+                "Cached asset {} is not a valid image, downloading it again",
+                path.display()
+            );
+
+            Ok(false)
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes an asset into an asset cache.
+///
+/// The asset is written to a temporary file first and then renamed, so that an
+/// interrupted write can't leave a partial image behind under the asset's name,
+/// and so that other processes reading the cache never see one either.
+/// Temporary files are named after the current process so that two processes
+/// writing the same asset don't clobber each other.
+pub(super) fn write_cached(cache_path: &Path, id: u64, contents: &[u8]) -> Result<(), SyncError> {
+    let temp_path = cache_path.join(format!("{}.{}.tmp", id, std::process::id()));
+
+    fs_err::write(&temp_path, contents)?;
+    fs_err::rename(&temp_path, cache_path.join(id.to_string()))?;
+
+    Ok(())
+}
+
+/// Puts an asset from the shared asset cache into a project's asset cache. The
+/// asset is hard linked if possible, which takes up no extra space, and is
+/// copied otherwise, like when the caches are on different drives.
+fn link_cached(shared_cache_path: &Path, cache_path: &Path, id: u64) -> Result<(), SyncError> {
+    let shared_path = shared_cache_path.join(id.to_string());
+    let temp_path = cache_path.join(format!("{}.{}.tmp", id, std::process::id()));
+
+    if let Err(err) = fs_err::hard_link(&shared_path, &temp_path) {
+        log::debug!("Copying asset ID {} from shared asset cache: {}", id, err);
+
+        fs_err::copy(&shared_path, &temp_path)?;
+    }
+
+    fs_err::rename(&temp_path, cache_path.join(id.to_string()))?;
+
+    Ok(())
+}
+
+/// Describes an error and everything that caused it on one line, for storing
+/// in the manifest.
+pub(super) fn describe_error(error: &SyncError) -> String {
+    let mut description = error.to_string();
+    let mut source = std::error::Error::source(error);
+
+    while let Some(cause) = source {
+        description.push_str(": ");
+        description.push_str(&cause.to_string());
+        source = cause.source();
+    }
+
+    description
+}
+
+/// Splits an image of the given size into a grid of tiles that are each no
+/// larger than `max_size`, row by row from the top left.
+fn tile_slices(size: (u32, u32), max_size: (u32, u32)) -> Vec<ImageSlice> {
+    let mut slices = Vec::new();
+
+    for y in (0..size.1).step_by(max_size.1 as usize) {
+        for x in (0..size.0).step_by(max_size.0 as usize) {
+            let max_x = x.saturating_add(max_size.0).min(size.0);
+            let max_y = y.saturating_add(max_size.1).min(size.1);
+
+            slices.push(ImageSlice::new((x, y), (max_x, max_y)));
+        }
+    }
+
+    slices
+}
+
+/// Tells whether two slices of a spritesheet share any pixels.
+fn slices_overlap(a: &ImageSlice, b: &ImageSlice) -> bool {
+    a.min().0 < b.max().0 && b.min().0 < a.max().0 && a.min().1 < b.max().1 && b.min().1 < a.max().1
+}
+
+/// Decodes the contents of an image input, making sure that any errors point
+/// back to the file that they came from.
+///
+/// Images are decoded based on their contents instead of their extension, so
+/// PNG images with the wrong extension still work.
+fn decode_input_image(path: &Path, contents: &[u8]) -> Result<Image, SyncError> {
+    if contents.is_empty() {
+        return Err(SyncError::ImageDecode {
+            path: path.to_owned(),
+            source: png::DecodingError::Other("the file is empty".into()),
+        });
+    }
 
-        _ => false,
+    if FileFormat::sniff(contents) == Some(FileFormat::Jpeg) {
+        return Err(SyncError::ImageDecode {
+            path: path.to_owned(),
+            source: png::DecodingError::Other(
+                "the file is a JPEG image, but only PNG images can be packed".into(),
+            ),
+        });
     }
+
+    Image::decode_png(contents).map_err(|source| SyncError::ImageDecode {
+        path: path.to_owned(),
+        source,
+    })
 }
 
 fn generate_asset_hash(content: &[u8]) -> String {
     format!("{}", blake3::hash(content).to_hex())
 }
 
+/// Hashes the decoded pixels of a spritesheet along with where each input was
+/// placed in it. Unlike hashing the encoded PNG, this doesn't change when a
+/// different PNG encoder produces different bytes for the same pixels.
+fn spritesheet_pixel_hash(packed_image: &PackedImage) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(packed_image.image.pixel_hash().as_bytes());
+
+    let mut slices: Vec<_> = packed_image.slices.iter().collect();
+    slices.sort_by_key(|&(name, _)| name);
+
+    for (name, slice) in slices {
+        let (min, max) = (slice.min(), slice.max());
+
+        hasher.update(name.as_ref().as_bytes());
+        hasher.update(&[0]);
+        for coordinate in &[min.0, min.1, max.0, max.1] {
+            hasher.update(&coordinate.to_le_bytes());
+        }
+    }
+
+    format!("{}", hasher.finalize().to_hex())
+}
+
 #[derive(Debug, Error)]
 pub enum SyncError {
     #[error("Path {} was described by more than one glob", .path.display())]
     OverlappingGlobs { path: PathBuf },
 
+    #[error(
+        "{} and {} both have the asset name '{name}', try a different asset-name-style",
+        .first.display(),
+        .second.display()
+    )]
+    DuplicateAssetName {
+        name: String,
+        first: PathBuf,
+        second: PathBuf,
+    },
+
+    #[error(
+        "DPI variants have mismatched sizes: {} is {}x{} at {}x, but {} is {}x{} at {}x",
+        .base_path.display(),
+        .base_size.0,
+        .base_size.1,
+        .base_scale,
+        .path.display(),
+        .size.0,
+        .size.1,
+        .dpi_scale
+    )]
+    DpiScaleMismatch {
+        base_path: PathBuf,
+        base_scale: u32,
+        base_size: (u32, u32),
+        path: PathBuf,
+        dpi_scale: u32,
+        size: (u32, u32),
+    },
+
+    #[error(
+        "{} has the extension of a {expected} image, but is actually a {actual} image",
+        .path.display()
+    )]
+    FormatMismatch {
+        path: PathBuf,
+        expected: FileFormat,
+        actual: FileFormat,
+    },
+
+    #[error(
+        "{count} input(s) matched by globs are not images that Tarmac recognizes and will not be synced: {examples}"
+    )]
+    UnrecognizedInputs { count: usize, examples: String },
+
+    #[error("No inputs were found. These globs matched nothing: {globs}")]
+    NoInputs { globs: String },
+
+    #[error(
+        "{} uploaded asset(s) have not been approved by moderation: {}",
+        .uploads.len(),
+        .uploads.join(", ")
+    )]
+    UnapprovedUploads { uploads: Vec<String> },
+
     #[error("'tarmac sync' completed, but with {error_count} error(s)")]
     HadErrors { error_count: usize },
 
+    #[error(
+        "Image {name} is {}x{}, which is too large to fit in a spritesheet of at most {}x{}",
+        .size.0,
+        .size.1,
+        .max_size.0,
+        .max_size.1
+    )]
+    ImageTooLarge {
+        name: AssetName,
+        size: (u32, u32),
+        max_size: (u32, u32),
+    },
+
+    #[error(
+        "min-spritesheet-size ({}x{}) is larger than max-spritesheet-size ({}x{})",
+        .min_size.0,
+        .min_size.1,
+        .max_size.0,
+        .max_size.1
+    )]
+    MinSpritesheetSizeTooLarge {
+        min_size: (u32, u32),
+        max_size: (u32, u32),
+    },
+
+    #[error("No config named or at path '{name}' is part of this project")]
+    UnknownConfig { name: String },
+
+    #[error(
+        "'tarmac sync' was canceled. Assets uploaded so far were saved to the manifest, so \
+         syncing again will pick up where this sync left off."
+    )]
+    Canceled,
+
+    #[error("Couldn't run post-sync-command '{command}'")]
+    PostSyncCommandSpawn { command: String, source: io::Error },
+
+    #[error("post-sync-command '{command}' failed with {status}")]
+    PostSyncCommandFailed { command: String, status: ExitStatus },
+
+    #[error("'tarmac sync' stopped at its first error because of --fail-fast")]
+    FailFast { source: anyhow::Error },
+
+    #[error(transparent)]
+    Pack {
+        #[from]
+        source: PackError,
+    },
+
     #[error(transparent)]
     WalkDir {
         #[from]
         source: walkdir::Error,
     },
 
+    #[error("Could not start workers to alpha bleed spritesheets")]
+    BleedWorkers {
+        #[from]
+        source: rayon::ThreadPoolBuildError,
+    },
+
     #[error(transparent)]
     Config {
         #[from]
         source: ConfigError,
     },
 
+    #[error(transparent)]
+    PathInfo {
+        #[from]
+        source: PathInfoError,
+    },
+
+    #[error(transparent)]
+    IgnoreFile {
+        #[from]
+        source: IgnoreFileError,
+    },
+
     #[error(transparent)]
     Backend {
         #[from]
@@ -737,9 +2863,9 @@ pub enum SyncError {
         source: io::Error,
     },
 
-    #[error(transparent)]
-    PngDecode {
-        #[from]
+    #[error("Could not decode image {}", .path.display())]
+    ImageDecode {
+        path: PathBuf,
         source: png::DecodingError,
     },
 
@@ -763,12 +2889,199 @@ pub enum SyncError {
 }
 
 impl SyncError {
-    pub fn is_rate_limited(&self) -> bool {
+    /// If this error is because Roblox rejected an upload, like for failing
+    /// moderation, returns the name that the asset was uploaded with.
+    pub fn rejected_asset(&self) -> Option<&str> {
         match self {
             Self::Backend {
-                source: SyncBackendError::RateLimited,
-            } => true,
-            _ => false,
+                source: SyncBackendError::Rejected { name, .. },
+            } => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(
+            self,
+            Self::Backend {
+                source: SyncBackendError::RateLimited { .. }
+                    | SyncBackendError::RetryDelayTooLong { .. },
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cached_assets_are_recognized_from_their_header() {
+        let cache_path =
+            std::env::temp_dir().join(format!("tarmac-cache-test-{}", std::process::id()));
+        fs::create_dir_all(&cache_path).unwrap();
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.resize(4096, 0);
+        fs::write(cache_path.join("1"), &png).unwrap();
+        fs::write(cache_path.join("2"), b"<!DOCTYPE html>").unwrap();
+        fs::write(cache_path.join("3"), b"\x89PN").unwrap();
+
+        assert!(is_cached(&cache_path, 1).unwrap());
+        assert!(!is_cached(&cache_path, 2).unwrap());
+        assert!(!is_cached(&cache_path, 3).unwrap());
+        assert!(!is_cached(&cache_path, 4).unwrap());
+
+        fs::remove_dir_all(&cache_path).unwrap();
+    }
+
+    /// Uploads the given number of assets, then fails every upload after that.
+    struct FlakyBackend {
+        uploads: usize,
+        successes: usize,
+    }
+
+    impl SyncBackend for FlakyBackend {
+        fn upload(&mut self, _data: UploadInfo) -> Result<UploadResponse, SyncBackendError> {
+            if self.uploads == self.successes {
+                let source = io::Error::other("upload failed");
+                return Err(SyncBackendError::Io { source });
+            }
+
+            self.uploads += 1;
+            Ok(UploadResponse {
+                id: self.uploads as u64,
+                moderation_state: None,
+            })
         }
     }
+
+    #[test]
+    fn tiles_are_kept_when_a_later_tile_fails() {
+        let project_dir =
+            std::env::temp_dir().join(format!("tarmac-tiles-test-{}", std::process::id()));
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(
+            project_dir.join("tarmac.toml"),
+            "name = \"test\"\nmax-spritesheet-size = [16, 16]\n\n\
+             [[inputs]]\nglob = \"big.png\"\nallow-tiling = true\n",
+        )
+        .unwrap();
+
+        // Every tile has different contents, so none of them are deduplicated.
+        let data: Vec<u8> = (0..40 * 16)
+            .flat_map(|i| [(i % 40) as u8, 0, 0, 255])
+            .collect();
+        let mut encoded = Vec::new();
+        Image::new_rgba8((40, 16), data)
+            .encode_png_with_compression(&mut encoded, PngCompression::Default)
+            .unwrap();
+        fs::write(project_dir.join("big.png"), encoded).unwrap();
+
+        let sync = |successes| {
+            let mut session = SyncSession::new(&project_dir).unwrap();
+            session.discover_configs().unwrap();
+            session.discover_inputs().unwrap();
+
+            let mut backend = FlakyBackend {
+                uploads: 0,
+                successes,
+            };
+            session.sync_with_backend(&mut backend);
+            session.write_manifest().unwrap();
+
+            (session, backend.uploads)
+        };
+
+        // Two of the three tiles are uploaded before the third one fails.
+        let (first, uploads) = sync(2);
+        assert_eq!(uploads, 2);
+        assert_eq!(first.sync_errors.len(), 1);
+
+        // Only the tile that failed needs to be uploaded again.
+        let (second, uploads) = sync(1);
+        assert_eq!(uploads, 1);
+        assert!(second.sync_errors.is_empty());
+
+        let manifest = Manifest::read_from_folder(&project_dir).unwrap();
+        let input = manifest.inputs.values().next().unwrap();
+        let ids: Vec<_> = input.tiles.iter().map(|tile| tile.id).collect();
+        assert_eq!(ids, [1, 2, 1]);
+        assert!(input.partial_tiles.is_empty());
+
+        fs::remove_dir_all(&project_dir).unwrap();
+    }
+
+    #[test]
+    fn tiles_near_the_largest_size() {
+        let slices = tile_slices((u32::MAX, 1), (u32::MAX - 1, 1));
+
+        assert_eq!(slices.len(), 2);
+        assert_eq!(slices[1].min(), (u32::MAX - 1, 0));
+        assert_eq!(slices[1].max(), (u32::MAX, 1));
+    }
+
+    #[test]
+    fn tiles_cover_image() {
+        let slices = tile_slices((2500, 1024), (1024, 1024));
+
+        let bounds: Vec<_> = slices
+            .iter()
+            .map(|slice| (slice.min(), slice.max()))
+            .collect();
+        assert_eq!(
+            bounds,
+            [
+                ((0, 0), (1024, 1024)),
+                ((1024, 0), (2048, 1024)),
+                ((2048, 0), (2500, 1024)),
+            ]
+        );
+    }
+
+    #[test]
+    fn overlapping_slices() {
+        let slice = ImageSlice::new((10, 10), (20, 20));
+
+        assert!(slices_overlap(&slice, &ImageSlice::new((15, 15), (25, 25))));
+        assert!(slices_overlap(&slice, &slice));
+
+        // Slices that only touch along an edge don't share any pixels.
+        assert!(!slices_overlap(
+            &slice,
+            &ImageSlice::new((20, 10), (30, 20))
+        ));
+        assert!(!slices_overlap(&slice, &ImageSlice::new((10, 0), (20, 10))));
+    }
+
+    #[test]
+    fn decode_empty_image() {
+        let path = Path::new("assets/empty.png");
+        let err = decode_input_image(path, &[]).unwrap_err();
+
+        assert!(err.to_string().contains(&path.display().to_string()));
+    }
+
+    #[test]
+    fn decode_jpeg_named_as_png() {
+        let path = Path::new("assets/photo.png");
+        let err = decode_input_image(path, b"\xff\xd8\xff\xe0").unwrap_err();
+
+        assert!(matches!(err, SyncError::ImageDecode { .. }));
+        assert!(format!("{:?}", err).contains("JPEG"));
+    }
+
+    #[test]
+    fn decode_truncated_image() {
+        let mut encoded = Vec::new();
+        Image::new_empty_rgba8((16, 16))
+            .encode_png_with_compression(&mut encoded, PngCompression::Default)
+            .unwrap();
+        encoded.truncate(encoded.len() / 2);
+
+        let path = Path::new("assets/truncated.png");
+        let err = decode_input_image(path, &encoded).unwrap_err();
+
+        assert!(err.to_string().contains(&path.display().to_string()));
+    }
 }
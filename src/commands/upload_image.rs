@@ -1,6 +1,13 @@
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+};
+
 use fs_err as fs;
+use thiserror::Error;
 
 use crate::{
+    data::Config,
     options::{GlobalOptions, UploadImageOptions},
     roblox_web_api::{RobloxApiClient, RobloxOpenCloudCredentials, IMAGE},
     roblox_web_api_types::{ImageUploadData, ImageUploadMetadata},
@@ -10,10 +17,26 @@ pub fn upload_image(
     global: GlobalOptions,
     options: UploadImageOptions,
 ) -> Result<(), anyhow::Error> {
-    let image_data = fs::read(options.path).expect("couldn't read input file");
+    let image_data = read_input(&options.path)?;
     let credentials = RobloxOpenCloudCredentials::get_credentials(global.auth, global.api_key)?;
 
-    let mut client = RobloxApiClient::new(credentials);
+    let mut client = RobloxApiClient::builder(credentials)
+        .proxy(global.proxy)
+        .root_certificate(global.root_certificate)
+        .csrf_retry(!global.no_csrf_retry)
+        .build()?;
+    client.check_api_key()?;
+
+    // If no creator was given, fall back to the group from the Tarmac project
+    // in the current directory, if there is one.
+    let group_id = match (options.user_id, options.group_id) {
+        (None, None) => match Config::read_from_folder(env::current_dir()?) {
+            Ok(config) => config.upload_to_group_id,
+            Err(err) if err.is_not_found() => None,
+            Err(err) => return Err(err.into()),
+        },
+        (_, group_id) => group_id,
+    };
 
     let upload_data = ImageUploadData {
         image_data: image_data.into(),
@@ -22,13 +45,39 @@ pub fn upload_image(
             options.name.to_string(),
             options.description.to_string(),
             options.user_id,
-            options.group_id,
+            group_id,
         )?,
     };
 
     let response = client.upload_image(upload_data)?;
 
-    eprintln!("Image uploaded successfully!");
+    if !global.quiet {
+        eprintln!("Image uploaded successfully!");
+    }
     println!("{}", response.asset_id);
     Ok(())
 }
+
+fn read_input(path: &Path) -> Result<Vec<u8>, UploadImageError> {
+    fs::read(path).map_err(|source| {
+        if source.kind() == io::ErrorKind::NotFound {
+            UploadImageError::InputNotFound {
+                path: path.to_owned(),
+            }
+        } else {
+            UploadImageError::ReadInput {
+                path: path.to_owned(),
+                source,
+            }
+        }
+    })
+}
+
+#[derive(Debug, Error)]
+pub enum UploadImageError {
+    #[error("Input image not found: {}", .path.display())]
+    InputNotFound { path: PathBuf },
+
+    #[error("Couldn't read input image {}", .path.display())]
+    ReadInput { path: PathBuf, source: io::Error },
+}
@@ -0,0 +1,222 @@
+//! Draws a contact sheet: a grid of labeled thumbnails of every image in a
+//! project, for reviewing a project's art all at once.
+
+use crate::{
+    asset_name::AssetName,
+    bitmap_font::GLYPH_WIDTH,
+    image::{Image, Pixel, ResizeFilter},
+};
+
+/// The space around each thumbnail, in pixels.
+const PADDING: u32 = 8;
+
+/// How large each pixel of the label font is drawn.
+const TEXT_SCALE: u32 = 2;
+
+/// The size of the squares in the checkerboard drawn behind each thumbnail,
+/// which makes transparent parts of images visible.
+const CHECKER_SIZE: u32 = 8;
+
+const BACKGROUND: Pixel = Pixel {
+    r: 32,
+    g: 32,
+    b: 32,
+    a: 255,
+};
+const CHECKER_LIGHT: Pixel = Pixel {
+    r: 204,
+    g: 204,
+    b: 204,
+    a: 255,
+};
+const CHECKER_DARK: Pixel = Pixel {
+    r: 153,
+    g: 153,
+    b: 153,
+    a: 255,
+};
+const LABEL: Pixel = Pixel {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 255,
+};
+
+/// Lays out the given images in a grid with `columns` columns, or a roughly
+/// square grid if that isn't given. Each image is scaled down to fit in a
+/// `thumbnail_size` square with its filter and labeled with its name.
+pub(crate) fn contact_sheet(
+    images: &[(AssetName, Image, ResizeFilter)],
+    thumbnail_size: u32,
+    columns: Option<u32>,
+) -> Image {
+    let count = images.len() as u32;
+    let columns = columns
+        .unwrap_or_else(|| (count as f64).sqrt().ceil() as u32)
+        .max(1);
+    let rows = count.div_ceil(columns);
+
+    let (_, label_height) = Image::text_size("", TEXT_SCALE);
+    let cell_size = (
+        thumbnail_size + PADDING,
+        thumbnail_size + label_height + PADDING * 2,
+    );
+
+    let mut sheet = Image::new_filled_rgba8(
+        (
+            columns * cell_size.0 + PADDING,
+            rows * cell_size.1 + PADDING,
+        ),
+        BACKGROUND,
+    );
+
+    for (index, (name, image, filter)) in images.iter().enumerate() {
+        let index = index as u32;
+        let x = PADDING + (index % columns) * cell_size.0;
+        let y = PADDING + (index / columns) * cell_size.1;
+
+        let thumbnail = thumbnail(image, thumbnail_size, *filter);
+        let (width, height) = thumbnail.size();
+
+        // Thumbnails are centered in their cells. They can only be larger than
+        // their cells when the cells are too small to hold even a single pixel.
+        let pos = (
+            x + thumbnail_size.saturating_sub(width) / 2,
+            y + thumbnail_size.saturating_sub(height) / 2,
+        );
+
+        draw_checkerboard(&mut sheet, pos, thumbnail.size());
+        draw_over(&mut sheet, &thumbnail, pos);
+
+        let label = fit_label(name.as_ref(), thumbnail_size);
+        sheet.draw_text((x, y + thumbnail_size + PADDING), &label, TEXT_SCALE, LABEL);
+    }
+
+    sheet
+}
+
+/// Scales an image down to fit in a square with sides of `max_size`, keeping
+/// its aspect ratio. Images that already fit are left at their size.
+fn thumbnail(image: &Image, max_size: u32, filter: ResizeFilter) -> Image {
+    let (width, height) = image.size();
+
+    if width <= max_size && height <= max_size {
+        return image.clone();
+    }
+
+    let scale = max_size as f64 / width.max(height) as f64;
+    let size = (
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    );
+
+    image.resize(size, filter)
+}
+
+/// Shortens a label to fit in the given width, keeping its end, which is
+/// usually the file name and so the most useful part of an asset's name.
+fn fit_label(label: &str, width: u32) -> String {
+    // Each character takes up its glyph and a column of space, except the
+    // last one, which doesn't need the space.
+    let max_chars = ((width / TEXT_SCALE + 1) / (GLYPH_WIDTH + 1)) as usize;
+
+    let chars: Vec<char> = label.chars().collect();
+    if chars.len() <= max_chars {
+        return label.to_owned();
+    }
+
+    let kept = max_chars.saturating_sub(2);
+    let mut fitted = "..".to_owned();
+    fitted.extend(&chars[chars.len() - kept..]);
+    fitted
+}
+
+fn draw_checkerboard(sheet: &mut Image, pos: (u32, u32), size: (u32, u32)) {
+    for y in 0..size.1 {
+        for x in 0..size.0 {
+            let light = (x / CHECKER_SIZE + y / CHECKER_SIZE) % 2 == 0;
+            let pixel = if light { CHECKER_LIGHT } else { CHECKER_DARK };
+
+            sheet.set_pixel((pos.0 + x, pos.1 + y), pixel);
+        }
+    }
+}
+
+/// Draws an image on top of an opaque one, blending by the image's alpha.
+fn draw_over(sheet: &mut Image, image: &Image, pos: (u32, u32)) {
+    for (x, y, pixel) in image.pixels() {
+        let target = (pos.0 + x, pos.1 + y);
+        let below = sheet.get_pixel(target);
+
+        let alpha = pixel.a as u32;
+        let blend = |top: u8, bottom: u8| {
+            ((top as u32 * alpha + bottom as u32 * (255 - alpha) + 127) / 255) as u8
+        };
+
+        sheet.set_pixel(
+            target,
+            Pixel::new(
+                blend(pixel.r, below.r),
+                blend(pixel.g, below.g),
+                blend(pixel.b, below.b),
+                255,
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn grid_layout() {
+        let images: Vec<_> = ["a.png", "b.png", "c.png"]
+            .iter()
+            .map(|name| {
+                let image = Image::new_empty_rgba8((300, 100));
+                (AssetName::new(name), image, ResizeFilter::default())
+            })
+            .collect();
+
+        let sheet = contact_sheet(&images, 64, None);
+
+        // Two columns and two rows of 64 pixel thumbnails, with room for a
+        // label under each.
+        assert_eq!(sheet.size(), (2 * 72 + 8, 2 * (64 + 10 + 16) + 8));
+    }
+
+    #[test]
+    fn zero_thumbnail_size() {
+        let images = vec![(
+            AssetName::new("a.png"),
+            Image::new_empty_rgba8((300, 100)),
+            ResizeFilter::default(),
+        )];
+
+        let sheet = contact_sheet(&images, 0, None);
+
+        assert_eq!(sheet.size(), (8 + 8, 10 + 16 + 8));
+    }
+
+    #[test]
+    fn thumbnails_use_their_filter() {
+        let mut stripes = Image::new_filled_rgba8((4, 1), Pixel::new(0, 0, 0, 255));
+        stripes.set_pixel((1, 0), Pixel::new(255, 255, 255, 255));
+        stripes.set_pixel((3, 0), Pixel::new(255, 255, 255, 255));
+
+        let nearest = thumbnail(&stripes, 2, ResizeFilter::Nearest);
+        assert_eq!(nearest.get_pixel((0, 0)), Pixel::new(255, 255, 255, 255));
+
+        let bilinear = thumbnail(&stripes, 2, ResizeFilter::Bilinear);
+        assert_ne!(bilinear.get_pixel((0, 0)), Pixel::new(255, 255, 255, 255));
+    }
+
+    #[test]
+    fn long_labels_keep_their_end() {
+        let label = fit_label("ui/icons/really/long/path/close.png", 128);
+
+        assert_eq!(label, "..path/close.png");
+        assert!(Image::text_size(&label, TEXT_SCALE).0 <= 128);
+    }
+}
@@ -1,13 +1,19 @@
 use std::{
     io,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
 use fs_err as fs;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::glob::Glob;
+use crate::{
+    asset_name::AssetNameStyle,
+    data::MANIFEST_FILENAME,
+    glob::Glob,
+    image::{PngCompression, ResizeFilter},
+};
 
 static CONFIG_FILENAME: &str = "tarmac.toml";
 
@@ -21,24 +27,173 @@ pub struct Config {
     /// The name of the project, currently only used in debugging.
     pub name: String,
 
-    /// The maximum size that any packed spritesheets should be. Only applies if
+    /// The maximum size that spritesheets packed from this config's inputs
+    /// should be. Included configs that don't set this use the root config's
+    /// value.
+    pub max_spritesheet_size: Option<(u32, u32)>,
+
+    /// The size that spritesheets packed from this config's inputs start at
+    /// before growing to fit their images. Included configs that don't set
+    /// this use the root config's value.
+    pub min_spritesheet_size: Option<(u32, u32)>,
+
+    /// The color, as RGBA, that spritesheets packed from this config's inputs
+    /// are filled with before any images are packed into them. Included
+    /// configs that don't set this use the root config's value.
+    pub spritesheet_background: Option<(u8, u8, u8, u8)>,
+
+    /// Whether packed spritesheets should be identified by their pixels and
+    /// layout instead of their encoded PNG bytes. PNG encoders don't always
+    /// produce the same bytes for the same pixels, so this keeps spritesheets
+    /// whose pixels didn't change from being uploaded again. Only applies if
     /// this config is the root config file.
-    #[serde(default = "default_max_spritesheet_size")]
-    pub max_spritesheet_size: (u32, u32),
+    #[serde(default)]
+    pub hash_spritesheet_pixels: bool,
+
+    /// Whether alpha bleeding should average colors as linear light instead of
+    /// averaging their sRGB values. Only applies if this config is the root
+    /// config file.
+    #[serde(default)]
+    pub linear_blending: bool,
+
+    /// Whether spritesheets with changed images should be updated with every
+    /// image left where it was, instead of packing every spritesheet from
+    /// scratch. Only applies if this config is the root config file.
+    #[serde(default)]
+    pub update_spritesheets_in_place: bool,
+
+    /// The most images that Tarmac should pack into each spritesheet, for
+    /// runtime atlases that can only index so many sprites per sheet. Only
+    /// applies if this config is the root config file.
+    pub max_spritesheet_items: Option<usize>,
+
+    /// How hard Tarmac should try to make packed spritesheets small before
+    /// uploading them. Only applies if this config is the root config file.
+    #[serde(default)]
+    pub spritesheet_png_compression: PngCompression,
+
+    /// Whether packed spritesheets should be encoded without the channels they
+    /// don't need, like alpha when they're fully opaque. Only applies if this
+    /// config is the root config file.
+    #[serde(default)]
+    pub spritesheet_png_optimize_colors: bool,
+
+    /// The shape of the generated code for packed images. Only applies if this
+    /// config is the root config file.
+    #[serde(default)]
+    pub codegen_slice_layout: CodegenSliceLayout,
+
+    /// Whether code for inputs with `codegen_path` is generated as a folder of
+    /// ModuleScripts for Rojo instead of a single file. Only applies if this
+    /// config is the root config file.
+    #[serde(default)]
+    pub codegen_rojo_tree: bool,
+
+    /// Whether generated code for packed images should include their input's
+    /// `tags`. Only applies if this config is the root config file.
+    #[serde(default)]
+    pub codegen_tags: bool,
+
+    /// How Tarmac writes the names of assets, which key the manifest. Only
+    /// applies if this config is the root config file.
+    #[serde(default)]
+    pub asset_name_style: AssetNameStyle,
 
     /// A path to a folder where any assets contained in the project should be
     /// stored. Each asset's name will match its asset ID.
     pub asset_cache_path: Option<PathBuf>,
 
+    /// Globs matching files in `asset_cache_path` that Tarmac should never
+    /// clean up, even if they aren't assets in the project. Globs are matched
+    /// against file names. Only applies if this config is the root config file.
+    #[serde(default)]
+    pub asset_cache_keep: Vec<Glob>,
+
+    /// A path to a folder of downloaded assets that can be shared between many
+    /// projects, named by asset ID. Assets missing from `asset_cache_path` are
+    /// looked for here before being downloaded. Only applies if this config is
+    /// the root config file.
+    pub shared_asset_cache_path: Option<PathBuf>,
+
     /// A path to a file where Tarmac will write a list of all of the asset URLs
     /// referred to by this project.
     pub asset_list_path: Option<PathBuf>,
 
+    /// The format of the file at `asset_list_path`. Only applies if this config
+    /// is the root config file.
+    #[serde(default)]
+    pub asset_list_format: AssetListFormat,
+
+    /// A shell command to run after a sync finishes without any errors, like
+    /// for warming caches or sending notifications. It runs from the folder
+    /// containing this config. Only applies if this config is the root config
+    /// file.
+    pub post_sync_command: Option<String>,
+
+    /// A path to the manifest file that Tarmac uses to track what it has
+    /// synced. Defaults to `tarmac-manifest.toml` next to this config. Only
+    /// applies if this config is the root config file.
+    pub manifest_path: Option<PathBuf>,
+
     /// If specified, requires that all uploaded assets are uploaded to the
     /// given group. Attempting to sync will fail if the authenticated user does
     /// not have access to create assets on the group.
     pub upload_to_group_id: Option<u64>,
 
+    /// If specified, Tarmac will generate a module at this path that requires
+    /// every module generated for inputs that have `codegen-path` set. Only
+    /// applies if this config is the root config file.
+    pub codegen_index_path: Option<PathBuf>,
+
+    /// The Lua expression that `require` paths in the generated index module
+    /// are relative to. Since Roblox instance paths don't always mirror the
+    /// filesystem, this can be changed to match how modules are laid out in
+    /// the game.
+    #[serde(default = "default_codegen_require_prefix")]
+    pub codegen_require_prefix: String,
+
+    /// Whether generated Lua files should start with a comment saying that they
+    /// were generated by Tarmac. Only applies if this config is the root config
+    /// file.
+    #[serde(default = "default_codegen_header")]
+    pub codegen_header: bool,
+
+    /// What Tarmac should do when DPI variants of the same image, like
+    /// `logo.png` and `logo@2x.png`, have sizes that don't match their DPI
+    /// scales. Only applies if this config is the root config file.
+    #[serde(default)]
+    pub dpi_scale_mismatch: Severity,
+
+    /// The file extensions, without a leading dot, of inputs that Tarmac should
+    /// treat as images. Inputs with any other extension are unrecognized. Only
+    /// applies if this config is the root config file.
+    #[serde(default = "default_image_extensions")]
+    pub image_extensions: Vec<String>,
+
+    /// What Tarmac should do when inputs match files that aren't images that
+    /// Tarmac knows how to sync. Only applies if this config is the root
+    /// config file.
+    #[serde(default)]
+    pub unrecognized_inputs: Severity,
+
+    /// What Tarmac should do when an image's contents are in a different
+    /// format than its file extension says, like a JPEG named `.png`. Only
+    /// applies if this config is the root config file.
+    #[serde(default)]
+    pub format_mismatch: Severity,
+
+    /// Names of folders whose images should always be packed into
+    /// spritesheets, no matter what their input's `packable` says. Applies to
+    /// inputs from this config.
+    #[serde(default)]
+    pub packable_folders: Vec<String>,
+
+    /// Names of folders whose images should never be packed into
+    /// spritesheets, no matter what their input's `packable` says. Applies to
+    /// inputs from this config.
+    #[serde(default)]
+    pub unpackable_folders: Vec<String>,
+
     /// A list of paths that Tarmac should search in to find other Tarmac
     /// projects.
     ///
@@ -58,6 +213,18 @@ pub struct Config {
 }
 
 impl Config {
+    /// Whether the given path has one of the extensions in `image-extensions`.
+    pub fn is_image_path(&self, path: &Path) -> bool {
+        let extension = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(extension) => extension,
+            None => return false,
+        };
+
+        self.image_extensions
+            .iter()
+            .any(|allowed| allowed.trim_start_matches('.') == extension)
+    }
+
     pub fn read_from_folder_or_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
         let path = path.as_ref();
         let meta = fs::metadata(path)?;
@@ -80,25 +247,108 @@ impl Config {
         let path = path.as_ref();
         let contents = fs::read(path)?;
 
-        let mut config: Self = toml::from_slice(&contents).map_err(|source| ConfigError::Toml {
-            source,
-            path: path.to_owned(),
-        })?;
-        config.file_path = path.to_owned();
+        Self::from_slice(&contents, path)
+    }
+
+    /// Parses a config that didn't necessarily come from a file on disk, like
+    /// one piped in over stdin. Relative paths in it are resolved from the
+    /// folder containing `file_path`, which doesn't need to exist.
+    pub fn from_slice<P: AsRef<Path>>(contents: &[u8], file_path: P) -> Result<Self, ConfigError> {
+        let file_path = file_path.as_ref();
+
+        let mut config: Self = toml::from_slice(contents)
+            .map_err(|source| ConfigError::from_toml(source, contents, file_path))?;
+        config.file_path = file_path.to_owned();
         config.make_paths_absolute();
+        config.check_spritesheet_sizes()?;
 
         Ok(config)
     }
 
+    /// Rejects spritesheet sizes that packing and tiling can't work with, like
+    /// a dimension of zero.
+    fn check_spritesheet_sizes(&self) -> Result<(), ConfigError> {
+        let sizes = [
+            ("max-spritesheet-size", self.max_spritesheet_size),
+            ("min-spritesheet-size", self.min_spritesheet_size),
+        ];
+
+        for (field, size) in sizes.iter().copied() {
+            if let Some(size) = size {
+                let valid = |dimension| (1..=MAX_SPRITESHEET_DIMENSION).contains(&dimension);
+
+                if !valid(size.0) || !valid(size.1) {
+                    return Err(ConfigError::InvalidSpritesheetSize {
+                        path: self.file_path.clone(),
+                        field,
+                        size,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// The path that paths in this Config should be considered relative to.
     pub fn folder(&self) -> &Path {
         self.file_path.parent().unwrap()
     }
 
+    /// The path to the manifest file for this project.
+    pub fn manifest_file_path(&self) -> PathBuf {
+        match &self.manifest_path {
+            Some(path) => path.clone(),
+            None => self.folder().join(MANIFEST_FILENAME),
+        }
+    }
+
+    /// Decides whether the input at the given path should be packable based on
+    /// the names of the folders it's in, if any of them are listed in
+    /// `packable_folders` or `unpackable_folders`. The folder closest to the
+    /// input wins.
+    pub fn packable_by_folder(&self, path: &Path) -> Option<bool> {
+        if self.packable_folders.is_empty() && self.unpackable_folders.is_empty() {
+            return None;
+        }
+
+        let relative_path = path.strip_prefix(self.folder()).unwrap_or(path);
+        let folders = relative_path.parent()?.components().rev();
+
+        for folder in folders {
+            let name = match folder.as_os_str().to_str() {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if self
+                .packable_folders
+                .iter()
+                .any(|packable| packable == name)
+            {
+                return Some(true);
+            }
+
+            if self
+                .unpackable_folders
+                .iter()
+                .any(|unpackable| unpackable == name)
+            {
+                return Some(false);
+            }
+        }
+
+        None
+    }
+
     /// Turn all relative paths referenced from this config into absolute paths.
     fn make_paths_absolute(&mut self) {
         let base = self.file_path.parent().unwrap();
 
+        if let Some(manifest_path) = self.manifest_path.as_mut() {
+            make_absolute(manifest_path, base);
+        }
+
         if let Some(list_path) = self.asset_list_path.as_mut() {
             make_absolute(list_path, base);
         }
@@ -107,6 +357,14 @@ impl Config {
             make_absolute(cache_path, base);
         }
 
+        if let Some(shared_cache_path) = self.shared_asset_cache_path.as_mut() {
+            make_absolute(shared_cache_path, base);
+        }
+
+        if let Some(index_path) = self.codegen_index_path.as_mut() {
+            make_absolute(index_path, base);
+        }
+
         for include in &mut self.includes {
             make_absolute(include, base);
         }
@@ -116,13 +374,79 @@ impl Config {
                 make_absolute(codegen_path, base);
             }
 
+            if let Some(output_dir) = input.codegen_output_dir.as_mut() {
+                make_absolute(output_dir, base);
+            }
+
             make_absolute(&mut input.codegen_base_path, base);
         }
     }
 }
 
-fn default_max_spritesheet_size() -> (u32, u32) {
-    (1024, 1024)
+fn default_image_extensions() -> Vec<String> {
+    vec!["png".to_owned(), "jpg".to_owned()]
+}
+
+fn default_codegen_header() -> bool {
+    true
+}
+
+fn default_codegen_require_prefix() -> String {
+    "script.Parent".to_owned()
+}
+
+/// How Tarmac should treat a kind of problem that it finds in a project.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    /// Log a warning and continue syncing.
+    #[default]
+    Warn,
+
+    /// Fail the sync with an error.
+    Error,
+}
+
+/// How Tarmac writes lists of the assets in a project.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssetListFormat {
+    /// One asset per line, with nothing else.
+    #[default]
+    Plain,
+
+    /// A CSV file with a header, where each row has an asset's name and URL.
+    /// Inputs that share an asset, like packed images, each get their own row.
+    Csv,
+}
+
+impl FromStr for AssetListFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<AssetListFormat, Self::Err> {
+        match value {
+            "plain" => Ok(AssetListFormat::Plain),
+            "csv" => Ok(AssetListFormat::Csv),
+
+            _ => Err(String::from(
+                "Invalid asset list format. Valid options are plain and csv.",
+            )),
+        }
+    }
+}
+
+/// How generated code describes where a packed image is in its spritesheet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CodegenSliceLayout {
+    /// A table that can be assigned straight to an `ImageLabel`, with `Image`,
+    /// `ImageRectOffset`, and `ImageRectSize`.
+    #[default]
+    ImageLabel,
+
+    /// A compact table with an `id` and a `rect` of `{x, y, width, height}`,
+    /// for runtime atlas libraries.
+    Rect,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -145,6 +469,12 @@ pub struct InputConfig {
     #[serde(default)]
     pub codegen_base_path: PathBuf,
 
+    /// If specified and `codegen_path` isn't, generated Lua files for this
+    /// group of inputs are written into this folder instead of next to their
+    /// images. Their paths relative to `codegen_base_path` are kept.
+    #[serde(default)]
+    pub codegen_output_dir: Option<PathBuf>,
+
     /// Whether the assets affected by this config are allowed to be packed into
     /// spritesheets.
     ///
@@ -154,8 +484,58 @@ pub struct InputConfig {
     /// instances.
     #[serde(default)]
     pub packable: bool,
+
+    /// Whether packable assets affected by this config should have their fully
+    /// transparent margins trimmed away before being packed. Generated code
+    /// will include the original size and offset of trimmed images.
+    #[serde(default)]
+    pub trim: bool,
+
+    /// Whether images from this group that aren't packed and are larger than
+    /// the max spritesheet size may be split into a grid of tiles, each
+    /// uploaded as its own asset. Off by default so that an image is never
+    /// split up without asking.
+    #[serde(default)]
+    pub allow_tiling: bool,
+
+    /// The ID of an existing Image asset to use as the preview, like the
+    /// catalog thumbnail, of each asset uploaded from this group of inputs.
+    /// Packed images don't get previews, since they're uploaded as part of a
+    /// spritesheet.
+    #[serde(default)]
+    pub preview_asset_id: Option<u64>,
+
+    /// The alt text for `preview_asset_id`. Defaults to the asset's name.
+    #[serde(default)]
+    pub preview_alt_text: Option<String>,
+
+    /// Arbitrary labels for the assets in this input group, like `ui` or
+    /// `deprecated`, that are recorded in the manifest for other tools.
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// The name of the atlas that packable images from this input group belong
+    /// to. Images in different atlases are never packed into the same
+    /// spritesheet.
+    #[serde(default)]
+    pub atlas_group: Option<String>,
+
+    /// The empty space that packable images from this input group keep on
+    /// every side of them in their spritesheet, instead of the single pixel
+    /// that's kept to the right of and below every image by default.
+    #[serde(default)]
+    pub padding: Option<u32>,
+
+    /// The filter to use when Tarmac scales images from this input group down,
+    /// like for thumbnails in `tarmac contact-sheet`.
+    #[serde(default)]
+    pub resize_filter: ResizeFilter,
 }
 
+/// The largest width or height that a config can give spritesheets. Much larger
+/// spritesheets would take gigabytes of memory to pack.
+pub const MAX_SPRITESHEET_DIMENSION: u32 = 16384;
+
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("Error deserializing TOML from path {}", .path.display())]
@@ -164,6 +544,32 @@ pub enum ConfigError {
         source: toml::de::Error,
     },
 
+    #[error(
+        "Unknown field `{field}` in config {}{}{}",
+        .path.display(),
+        .line.map(|line| format!(" on line {}", line)).unwrap_or_default(),
+        .suggestion.as_ref().map(|field| format!(", did you mean `{}`?", field)).unwrap_or_default()
+    )]
+    UnknownField {
+        path: PathBuf,
+        field: String,
+        line: Option<usize>,
+        suggestion: Option<String>,
+    },
+
+    #[error(
+        "`{field}` in config {} is {}x{}, but each dimension must be between 1 and {}",
+        .path.display(),
+        .size.0,
+        .size.1,
+        MAX_SPRITESHEET_DIMENSION
+    )]
+    InvalidSpritesheetSize {
+        path: PathBuf,
+        field: &'static str,
+        size: (u32, u32),
+    },
+
     #[error(transparent)]
     Io {
         #[from]
@@ -172,6 +578,37 @@ pub enum ConfigError {
 }
 
 impl ConfigError {
+    /// Turns an error from parsing a config into a ConfigError. Unknown fields,
+    /// which are usually typos, get their own error that names the field and
+    /// suggests the field that was probably meant, instead of listing every
+    /// field that a config can have.
+    fn from_toml(source: toml::de::Error, contents: &[u8], path: &Path) -> Self {
+        // toml doesn't expose what kind of error it hit, so this relies on the
+        // message that serde generates for unknown fields, which puts the
+        // field first and then every expected field, all in backticks.
+        let message = source.to_string();
+
+        if message.starts_with("unknown field `") {
+            let mut quoted = message.split('`').skip(1).step_by(2);
+
+            if let Some(field) = quoted.next() {
+                let suggestion = suggest_field(field, quoted);
+
+                return ConfigError::UnknownField {
+                    path: path.to_owned(),
+                    field: field.to_owned(),
+                    line: find_key_line(contents, field),
+                    suggestion: suggestion.map(str::to_owned),
+                };
+            }
+        }
+
+        ConfigError::Toml {
+            path: path.to_owned(),
+            source,
+        }
+    }
+
     /// Tells whether this ConfigError originated because of a path not
     /// existing.
     ///
@@ -185,6 +622,63 @@ impl ConfigError {
     }
 }
 
+/// Finds the first line of a config that assigns to the given key. toml only
+/// reports where the table containing an unknown field starts, which isn't
+/// much help in a long config.
+fn find_key_line(contents: &[u8], key: &str) -> Option<usize> {
+    let contents = std::str::from_utf8(contents).ok()?;
+
+    contents
+        .lines()
+        .position(|line| {
+            line.trim_start()
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        })
+        .map(|index| index + 1)
+}
+
+/// Picks the expected field that an unknown field was most likely meant to be,
+/// if any are close enough. Fields written in snake_case instead of kebab-case
+/// are the most common mistake.
+fn suggest_field<'a>(field: &str, expected: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let kebab_case = field.replace('_', "-");
+
+    expected
+        .map(|candidate| {
+            let distance = if candidate == kebab_case {
+                0
+            } else {
+                edit_distance(&kebab_case, candidate)
+            };
+
+            (distance, candidate)
+        })
+        .filter(|&(distance, _)| distance <= 2)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of single character insertions, deletions, or substitutions
+/// needed to turn one string into another.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
 /// Utility to make a path absolute if it is not absolute already.
 fn make_absolute(path: &mut PathBuf, base: &Path) {
     if path.is_relative() {
@@ -192,3 +686,142 @@ fn make_absolute(path: &mut PathBuf, base: &Path) {
         *path = new_path;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unknown_field_in_snake_case() {
+        let err = Config::from_slice(
+            b"name = \"test\"\n\n[[inputs]]\nglob = \"*.png\"\ncodegen_path = \"assets.lua\"\n",
+            "/project/tarmac.toml",
+        )
+        .unwrap_err();
+
+        match &err {
+            ConfigError::UnknownField {
+                field,
+                line,
+                suggestion,
+                ..
+            } => {
+                assert_eq!(field, "codegen_path");
+                assert_eq!(*line, Some(5));
+                assert_eq!(suggestion.as_deref(), Some("codegen-path"));
+            }
+            _ => panic!("expected an unknown field error, got {:?}", err),
+        }
+    }
+
+    #[test]
+    fn unknown_field_without_suggestion() {
+        let err =
+            Config::from_slice(b"name = \"test\"\nflavor = \"lime\"\n", "tarmac.toml").unwrap_err();
+
+        assert!(
+            matches!(&err, ConfigError::UnknownField { field, suggestion: None, .. } if field == "flavor")
+        );
+    }
+
+    #[test]
+    fn zero_spritesheet_size() {
+        let err = Config::from_slice(
+            b"name = \"test\"\nmax-spritesheet-size = [0, 1024]\n",
+            "tarmac.toml",
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            err,
+            ConfigError::InvalidSpritesheetSize {
+                field: "max-spritesheet-size",
+                size: (0, 1024),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn oversized_spritesheet_size() {
+        let err = Config::from_slice(
+            b"name = \"test\"\nmin-spritesheet-size = [32, 4294967295]\n",
+            "tarmac.toml",
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            err,
+            ConfigError::InvalidSpritesheetSize {
+                field: "min-spritesheet-size",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn misspelled_field() {
+        let expected = ["max-spritesheet-size", "min-spritesheet-size", "name"];
+
+        assert_eq!(
+            suggest_field("max-spritsheet-size", expected.iter().copied()),
+            Some("max-spritesheet-size")
+        );
+        assert_eq!(
+            suggest_field("nmae", expected.iter().copied()),
+            Some("name")
+        );
+    }
+
+    #[test]
+    fn packable_by_folder() {
+        let mut config: Config = toml::from_str(
+            r#"
+            name = "test"
+            packable-folders = ["spritesheet"]
+            unpackable-folders = ["decals"]
+            "#,
+        )
+        .unwrap();
+        config.file_path = PathBuf::from("/project/tarmac.toml");
+
+        let packable = |path: &str| config.packable_by_folder(Path::new(path));
+
+        assert_eq!(packable("/project/ui/spritesheet/close.png"), Some(true));
+        assert_eq!(packable("/project/decals/logo.png"), Some(false));
+        assert_eq!(packable("/project/decals/spritesheet/icon.png"), Some(true));
+        assert_eq!(
+            packable("/project/spritesheet/decals/icon.png"),
+            Some(false)
+        );
+        assert_eq!(packable("/project/ui/close.png"), None);
+
+        // The config's own folder doesn't count.
+        let mut nested = config.clone();
+        nested.file_path = PathBuf::from("/decals/tarmac.toml");
+        assert_eq!(
+            nested.packable_by_folder(Path::new("/decals/close.png")),
+            None
+        );
+    }
+
+    #[test]
+    fn image_extensions() {
+        let default: Config = toml::from_str(r#"name = "test""#).unwrap();
+        assert!(default.is_image_path(Path::new("a.png")));
+        assert!(default.is_image_path(Path::new("a.jpg")));
+        assert!(!default.is_image_path(Path::new("a.txt")));
+        assert!(!default.is_image_path(Path::new("png")));
+
+        let custom: Config = toml::from_str(
+            r#"
+            name = "test"
+            image-extensions = ["png", ".webp"]
+            "#,
+        )
+        .unwrap();
+        assert!(custom.is_image_path(Path::new("a.png")));
+        assert!(custom.is_image_path(Path::new("a.webp")));
+        assert!(!custom.is_image_path(Path::new("a.jpg")));
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+use thiserror::Error;
+
+use crate::glob::{self, Glob};
+
+static IGNORE_FILENAME: &str = ".tarmacignore";
+
+/// A list of globs read from a `.tarmacignore` file next to a config. Inputs
+/// matching any of them are skipped, even if an input glob matches them.
+///
+/// Each line is a glob relative to the config's folder. Blank lines and lines
+/// starting with `#` are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreFile {
+    globs: Vec<Glob>,
+}
+
+impl IgnoreFile {
+    /// Reads the ignore file in the given folder, if there is one.
+    pub fn read_from_folder<P: AsRef<Path>>(folder_path: P) -> Result<Self, IgnoreFileError> {
+        let file_path = folder_path.as_ref().join(IGNORE_FILENAME);
+
+        let contents = match fs::read_to_string(&file_path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => return Err(IgnoreFileError::Io { source }),
+        };
+
+        Self::parse(&contents).map_err(|source| IgnoreFileError::Glob { file_path, source })
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, glob::Error> {
+        let globs = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| Glob::new(line.trim_end_matches('/')))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { globs })
+    }
+
+    /// Tells whether the given path, relative to the ignore file's folder, is
+    /// ignored. Paths inside of ignored folders are ignored too.
+    pub fn is_ignored(&self, relative_path: &Path) -> bool {
+        relative_path
+            .ancestors()
+            .filter(|path| !path.as_os_str().is_empty())
+            .any(|path| self.globs.iter().any(|glob| glob.is_match(path)))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum IgnoreFileError {
+    #[error("Invalid glob in {}", .file_path.display())]
+    Glob {
+        file_path: PathBuf,
+        source: glob::Error,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ignores_matching_files_and_folders() {
+        let ignore = IgnoreFile::parse(
+            "
+            # Work in progress art
+            scratch/
+            **/*-old.png
+            ",
+        )
+        .unwrap();
+
+        assert!(ignore.is_ignored(Path::new("scratch/logo.png")));
+        assert!(ignore.is_ignored(Path::new("scratch/nested/logo.png")));
+        assert!(ignore.is_ignored(Path::new("assets/logo-old.png")));
+
+        assert!(!ignore.is_ignored(Path::new("assets/logo.png")));
+        assert!(!ignore.is_ignored(Path::new("assets/scratch.png")));
+    }
+
+    #[test]
+    fn empty_ignores_nothing() {
+        let ignore = IgnoreFile::parse("\n# Nothing to see here\n").unwrap();
+
+        assert!(!ignore.is_ignored(Path::new("assets/logo.png")));
+    }
+}
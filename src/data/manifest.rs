@@ -5,24 +5,48 @@ use std::{
 };
 
 use fs_err as fs;
+use packos::OutputItem;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::asset_name::AssetName;
+use crate::asset_name::{AssetName, AssetNameStyle};
 
-static MANIFEST_FILENAME: &str = "tarmac-manifest.toml";
+/// The name of the manifest file that Tarmac uses when a project doesn't
+/// specify `manifest-path`.
+pub static MANIFEST_FILENAME: &str = "tarmac-manifest.toml";
 
 /// Tracks the status of all configuration, inputs, and outputs as of the last
 /// sync operation.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct Manifest {
+    /// The style that the names of inputs were written in when this manifest
+    /// was written. Manifests from before names had styles use paths.
+    #[serde(default, skip_serializing_if = "AssetNameStyle::is_path")]
+    pub asset_name_style: AssetNameStyle,
+
     pub inputs: BTreeMap<AssetName, InputManifest>,
+
+    /// The asset IDs of uploaded spritesheets, keyed by the hash of their
+    /// pixels and layout. Only populated when `hash-spritesheet-pixels` is
+    /// enabled.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub spritesheets: BTreeMap<String, u64>,
+
+    /// The asset IDs of uploaded spritesheets, keyed by the hash of their
+    /// encoded PNG files. Spritesheets packed in a later sync with exactly the
+    /// same contents reuse these assets instead of being uploaded again.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub spritesheet_hashes: BTreeMap<String, u64>,
 }
 
 impl Manifest {
     pub fn read_from_folder<P: AsRef<Path>>(folder_path: P) -> Result<Self, ManifestError> {
-        let folder_path = folder_path.as_ref();
-        let file_path = &folder_path.join(MANIFEST_FILENAME);
+        Self::read_from_file(folder_path.as_ref().join(MANIFEST_FILENAME))
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(file_path: P) -> Result<Self, ManifestError> {
+        let file_path = file_path.as_ref();
 
         let contents = fs::read(file_path)?;
         let config =
@@ -34,9 +58,14 @@ impl Manifest {
         Ok(config)
     }
 
-    pub fn write_to_folder<P: AsRef<Path>>(&self, folder_path: P) -> Result<(), ManifestError> {
-        let folder_path = folder_path.as_ref();
-        let file_path = &folder_path.join(MANIFEST_FILENAME);
+    pub fn write_to_file<P: AsRef<Path>>(&self, file_path: P) -> Result<(), ManifestError> {
+        let file_path = file_path.as_ref();
+
+        // The manifest can be configured to live outside of the project, like
+        // in a build cache, so its folder might not exist yet.
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
 
         let serialized = toml::to_vec(self)?;
         fs::write(file_path, serialized)?;
@@ -64,6 +93,53 @@ pub struct InputManifest {
     /// Whether the config applied to this input asked for it to be packed into
     /// a spritesheet.
     pub packable: bool,
+
+    /// Whether the config applied to this input asked for its transparent
+    /// margins to be trimmed before packing.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub trim: bool,
+
+    /// If the image had its transparent margins trimmed before it was packed,
+    /// contains the information needed to reconstruct the original image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trim_info: Option<TrimInfo>,
+
+    /// Whether the config applied to this input allowed it to be split into
+    /// tiles when it's too large to upload as one image.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub allow_tiling: bool,
+
+    /// If the image was too large to upload as one image and was split into
+    /// tiles, contains each uploaded tile and where it is in the image.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiles: Vec<ImageTile>,
+
+    /// The atlas that the config applied to this input put it in, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub atlas_group: Option<String>,
+
+    /// The padding that the config applied to this input asked for, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub padding: Option<u32>,
+
+    /// Why this input's last upload failed, if it did. Cleared once the input
+    /// is uploaded successfully.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+
+    /// The tags from the config applied to this input.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// If a sync stopped partway through uploading the tiles of a newer
+    /// version of this image, contains the asset IDs of the tiles that were
+    /// uploaded, keyed by the hash of their encoded PNG files. The next sync
+    /// reuses these instead of uploading them again.
+    ///
+    /// This is a table, so it has to come after every plain value for the
+    /// manifest to be written as TOML.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub partial_tiles: BTreeMap<String, u64>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -95,6 +171,45 @@ impl ImageSlice {
     }
 }
 
+/// The slice of a spritesheet where a packed image was placed.
+///
+/// Packing never rotates images, and trimmed images are packed at their
+/// trimmed size, so the slice covers exactly the item's rectangle. Where the
+/// trimmed pixels were in the original image is tracked by `TrimInfo`.
+impl From<&OutputItem> for ImageSlice {
+    fn from(item: &OutputItem) -> Self {
+        Self::new(item.min(), item.max())
+    }
+}
+
+/// One piece of an image that was split into tiles, uploaded as its own asset.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ImageTile {
+    /// The asset ID that this tile was uploaded to.
+    pub id: u64,
+
+    /// The portion of the original image that this tile contains.
+    pub slice: ImageSlice,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Describes where a trimmed image was located within the original, untrimmed
+/// image, similar to TexturePacker's `spriteSourceSize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TrimInfo {
+    /// The size of the image before it was trimmed.
+    pub original_size: (u32, u32),
+
+    /// The position of the top-left corner of the trimmed image within the
+    /// original image.
+    pub offset: (u32, u32),
+}
+
 #[derive(Debug, Error)]
 pub enum ManifestError {
     #[error("Error deserializing TOML from path {}", .file_path.display())]
@@ -124,3 +239,27 @@ impl ManifestError {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use packos::{InputItem, SimplePacker};
+
+    #[test]
+    fn slices_cover_packed_items() {
+        let items = [InputItem::new((16, 8)), InputItem::new((4, 12))];
+        let output = SimplePacker::new()
+            .min_size((32, 32))
+            .padding(1)
+            .pack(items)
+            .unwrap();
+
+        for item in output.buckets()[0].items() {
+            let slice = ImageSlice::from(item);
+
+            assert_eq!(slice.min(), item.position());
+            assert_eq!(slice.size(), item.size());
+        }
+    }
+}
@@ -1,7 +1,9 @@
 mod config;
+mod ignore;
 mod manifest;
 mod sync;
 
 pub use config::*;
+pub use ignore::*;
 pub use manifest::*;
 pub use sync::*;
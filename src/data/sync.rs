@@ -1,8 +1,9 @@
-use std::path::PathBuf;
+use std::{fmt, path::PathBuf};
 
 use crate::{
     asset_name::AssetName,
-    data::{ImageSlice, InputConfig, InputManifest},
+    data::{ImageSlice, ImageTile, InputConfig, InputManifest, TrimInfo},
+    roblox_web_api_types::AssetPreview,
 };
 
 /// In-memory representation of a Tarmac Input during the sync process.
@@ -27,12 +28,21 @@ pub struct SyncInput {
     /// The configuration that applied to this input when it was discovered.
     pub config: InputConfig,
 
+    /// The index of the config that this input was discovered from, in the
+    /// order that the sync session discovered configs. The root config is
+    /// always 0.
+    pub config_index: usize,
+
     /// The contents of the file this input originated from.
     pub contents: Vec<u8>,
 
     /// A hash of `contents`.
     pub hash: String,
 
+    /// The size of this input in pixels, if it's an image that could be read
+    /// when it was discovered.
+    pub dimensions: Option<(u32, u32)>,
+
     /// If this input has been part of an upload to Roblox.com, contains the
     /// asset ID that contains the data from this input.
     pub id: Option<u64>,
@@ -40,11 +50,50 @@ pub struct SyncInput {
     /// If this input has been packed into a spritesheet, contains the slice of
     /// the spritesheet that this input is located in.
     pub slice: Option<ImageSlice>,
+
+    /// If this input was trimmed before being packed into a spritesheet,
+    /// contains where the trimmed image was within the original image.
+    pub trim_info: Option<TrimInfo>,
+
+    /// If this input was split into tiles because it was too large to upload
+    /// as one image, contains each of its tiles. `id` is the first tile's.
+    pub tiles: Vec<ImageTile>,
 }
 
 impl SyncInput {
-    pub fn is_unchanged_since_last_sync(&self, old_manifest: &InputManifest) -> bool {
-        self.hash == old_manifest.hash && self.config.packable == old_manifest.packable
+    /// Compares this input against what was recorded about it during the last
+    /// sync to decide whether it needs to be uploaded again.
+    pub fn status_since_last_sync(&self, old_manifest: Option<&InputManifest>) -> InputStatus {
+        let old_manifest = match old_manifest {
+            Some(old_manifest) => old_manifest,
+            None => return InputStatus::Added,
+        };
+
+        // Trimming, atlases, and padding only apply to images that are packed,
+        // and tiling only to images that aren't.
+        let config_changed = self.config.packable != old_manifest.packable
+            || (self.config.packable
+                && (self.config.trim != old_manifest.trim
+                    || self.config.atlas_group != old_manifest.atlas_group
+                    || self.config.padding != old_manifest.padding))
+            || (!self.config.packable && self.config.allow_tiling != old_manifest.allow_tiling);
+
+        if self.hash != old_manifest.hash {
+            InputStatus::ContentsChanged
+        } else if config_changed {
+            InputStatus::ConfigChanged
+        } else if self.id.is_none() {
+            InputStatus::NeverUploaded
+        } else {
+            InputStatus::Unchanged
+        }
+    }
+
+    /// Every asset ID that this input was uploaded to, including its tiles.
+    pub fn asset_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.id
+            .into_iter()
+            .chain(self.tiles.iter().map(|tile| tile.id))
     }
 
     /// Creates a non-unique, human-friendly name to refer to this input.
@@ -62,4 +111,64 @@ impl SyncInput {
             format!("{} ({}x)", file_stem, self.dpi_scale)
         }
     }
+
+    /// The preview image that should be attached to this input when it's
+    /// uploaded, as configured by its input group.
+    pub fn preview(&self) -> Option<AssetPreview> {
+        let asset_id = self.config.preview_asset_id?;
+        let alt_text = self
+            .config
+            .preview_alt_text
+            .clone()
+            .unwrap_or_else(|| self.human_name());
+
+        Some(AssetPreview::new(asset_id, alt_text))
+    }
+}
+
+/// Describes whether an input has changed since the last sync, and so whether
+/// it needs to be uploaded again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputStatus {
+    /// Nothing has changed since the last sync.
+    Unchanged,
+
+    /// The input was added since the last sync, if there was one.
+    Added,
+
+    /// The input's contents have been edited since the last sync.
+    ContentsChanged,
+
+    /// Only the config that applies to the input has changed.
+    ConfigChanged,
+
+    /// The input is in the manifest, but has never been uploaded.
+    NeverUploaded,
+}
+
+impl InputStatus {
+    pub fn is_unchanged(self) -> bool {
+        self == InputStatus::Unchanged
+    }
+
+    /// Tells whether the input has never been uploaded before.
+    pub fn is_new(self) -> bool {
+        matches!(self, InputStatus::Added | InputStatus::NeverUploaded)
+    }
+}
+
+impl fmt::Display for InputStatus {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            InputStatus::Unchanged => "it is unchanged since the last sync",
+            InputStatus::Added => "it was added since the last sync",
+            InputStatus::ContentsChanged => "its contents changed since the last sync",
+            InputStatus::ConfigChanged => {
+                "the config that applies to it changed since the last sync"
+            }
+            InputStatus::NeverUploaded => "it has never been uploaded",
+        };
+
+        formatter.write_str(message)
+    }
 }
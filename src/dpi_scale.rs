@@ -1,6 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use regex::Regex;
+use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct DpiAwarePathInfo {
@@ -20,44 +21,57 @@ impl DpiAwarePathInfo {
     }
 }
 
+#[derive(Debug, Error)]
+pub(crate) enum PathInfoError {
+    #[error("Path {} does not have a file name that is valid Unicode", .path.display())]
+    InvalidFileName { path: PathBuf },
+
+    #[error("Path {} has a DPI scale that is too large", .path.display())]
+    InvalidDpiScale { path: PathBuf },
+}
+
 /// Given a path, extracts its intended DPI scale and constructs a path without
 /// DPI scale information in it. This can be used to group together multiple
 /// versions of the same image.
-pub(crate) fn extract_path_info<P: AsRef<Path>>(path: P) -> DpiAwarePathInfo {
+pub(crate) fn extract_path_info<P: AsRef<Path>>(
+    path: P,
+) -> Result<DpiAwarePathInfo, PathInfoError> {
     lazy_static::lazy_static! {
         static ref DPI_PATTERN: Regex = Regex::new(r"^(.+?)@(\d+)x(.+?)$").unwrap();
     }
 
     let path = path.as_ref();
 
-    let file_name = match path.file_name().unwrap().to_str() {
-        Some(name) => name,
-
-        // If the filename isn't valid Unicode, this is an error.
-        None => {
-            panic!("Path {} had invalid Unicode", path.display());
-        }
-    };
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| PathInfoError::InvalidFileName {
+            path: path.to_owned(),
+        })?;
 
     match DPI_PATTERN.captures(file_name) {
         Some(captures) => {
             let file_stem = captures.get(1).unwrap().as_str().to_owned();
             let scale_str = captures.get(2).unwrap().as_str();
             let suffix = captures.get(3).unwrap().as_str();
-            let dpi_scale = scale_str.parse().unwrap();
+            let dpi_scale = scale_str
+                .parse()
+                .map_err(|_| PathInfoError::InvalidDpiScale {
+                    path: path.to_owned(),
+                })?;
 
             let file_name_without_dpi_scale = format!("{}{}", file_stem, suffix);
             let path_without_dpi_scale = path.with_file_name(&file_name_without_dpi_scale);
 
-            DpiAwarePathInfo {
+            Ok(DpiAwarePathInfo {
                 path_without_dpi_scale,
                 dpi_scale,
-            }
+            })
         }
-        None => DpiAwarePathInfo {
+        None => Ok(DpiAwarePathInfo {
             path_without_dpi_scale: path.to_owned(),
             dpi_scale: 1,
-        },
+        }),
     }
 }
 
@@ -68,17 +82,17 @@ mod test {
     #[test]
     fn no_attached_scale() {
         assert_eq!(
-            extract_path_info("foo.png"),
+            extract_path_info("foo.png").unwrap(),
             DpiAwarePathInfo::new("foo.png", 1)
         );
 
         assert_eq!(
-            extract_path_info("foo.blah.png"),
+            extract_path_info("foo.blah.png").unwrap(),
             DpiAwarePathInfo::new("foo.blah.png", 1)
         );
 
         assert_eq!(
-            extract_path_info("foo/bar/baz/hello.png"),
+            extract_path_info("foo/bar/baz/hello.png").unwrap(),
             DpiAwarePathInfo::new("foo/bar/baz/hello.png", 1)
         );
     }
@@ -86,45 +100,50 @@ mod test {
     #[test]
     fn explicit_1x() {
         assert_eq!(
-            extract_path_info("layerify@1x.png"),
+            extract_path_info("layerify@1x.png").unwrap(),
             DpiAwarePathInfo::new("layerify.png", 1)
         );
 
         assert_eq!(
-            extract_path_info("layerify.blah@1x.png"),
+            extract_path_info("layerify.blah@1x.png").unwrap(),
             DpiAwarePathInfo::new("layerify.blah.png", 1)
         );
 
         assert_eq!(
-            extract_path_info("layerify@1x.png.bak"),
+            extract_path_info("layerify@1x.png.bak").unwrap(),
             DpiAwarePathInfo::new("layerify.png.bak", 1)
         );
 
         assert_eq!(
-            extract_path_info("some/path/to/image/nice@1x.png"),
+            extract_path_info("some/path/to/image/nice@1x.png").unwrap(),
             DpiAwarePathInfo::new("some/path/to/image/nice.png", 1)
         );
     }
 
+    #[test]
+    fn dpi_scale_too_large() {
+        assert!(extract_path_info("huge@99999999999x.png").is_err());
+    }
+
     #[test]
     fn explicit_not_1x() {
         assert_eq!(
-            extract_path_info("cool-company@2x.png"),
+            extract_path_info("cool-company@2x.png").unwrap(),
             DpiAwarePathInfo::new("cool-company.png", 2)
         );
 
         assert_eq!(
-            extract_path_info("engineers@10x.png"),
+            extract_path_info("engineers@10x.png").unwrap(),
             DpiAwarePathInfo::new("engineers.png", 10)
         );
 
         assert_eq!(
-            extract_path_info("we.like.dots@3x.png"),
+            extract_path_info("we.like.dots@3x.png").unwrap(),
             DpiAwarePathInfo::new("we.like.dots.png", 3)
         );
 
         assert_eq!(
-            extract_path_info("backup-your-stuff@4x.png.bak"),
+            extract_path_info("backup-your-stuff@4x.png.bak").unwrap(),
             DpiAwarePathInfo::new("backup-your-stuff.png.bak", 4)
         );
     }
@@ -32,6 +32,24 @@ impl Glob {
     pub fn get_prefix(&self) -> PathBuf {
         get_non_pattern_prefix(Path::new(self.inner.glob()))
     }
+
+    /// Tells whether this glob is an absolute path, like `C:\art\*.png` or
+    /// `/art/*.png`, instead of being relative to a config.
+    pub fn is_absolute(&self) -> bool {
+        Path::new(self.inner.glob()).is_absolute()
+    }
+
+    /// The directory that needs to be searched to find every path matching this
+    /// glob, given the folder that relative globs are relative to.
+    pub fn get_walk_root(&self, base_path: &Path) -> PathBuf {
+        let prefix = self.get_prefix();
+
+        if prefix.is_absolute() {
+            prefix
+        } else {
+            base_path.join(prefix)
+        }
+    }
 }
 
 impl PartialEq for Glob {
@@ -119,6 +137,41 @@ mod test {
         );
     }
 
+    #[test]
+    fn relative_walk_root() {
+        let glob = Glob::new("assets/**/*.png").unwrap();
+
+        assert!(!glob.is_absolute());
+        assert_eq!(
+            glob.get_walk_root(Path::new("project")),
+            Path::new("project").join("assets")
+        );
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn absolute_walk_root() {
+        let glob = Glob::new("/art/**/*.png").unwrap();
+
+        assert!(glob.is_absolute());
+        assert_eq!(
+            glob.get_walk_root(Path::new("/project")),
+            PathBuf::from("/art")
+        );
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn absolute_walk_root() {
+        let glob = Glob::new("C:\\art\\**\\*.png").unwrap();
+
+        assert!(glob.is_absolute());
+        assert_eq!(
+            glob.get_walk_root(Path::new("C:\\project")),
+            PathBuf::from("C:\\art")
+        );
+    }
+
     #[test]
     fn whole_path() {
         assert_eq!(
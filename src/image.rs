@@ -1,6 +1,17 @@
 //! Simple containers to track images and perform operations on them.
 
-use std::io::{Read, Write};
+use std::{
+    convert::TryFrom,
+    fmt,
+    io::{self, Read, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::bitmap_font::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ImageFormat {
@@ -15,6 +26,120 @@ impl ImageFormat {
     }
 }
 
+/// Encoded image file formats that Tarmac can recognize from their contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FileFormat {
+    Png,
+    Jpeg,
+}
+
+impl FileFormat {
+    /// How many bytes from the start of a file `sniff` needs to recognize it.
+    pub const SNIFF_LEN: usize = 8;
+
+    /// Guesses the format of an encoded image by looking at its magic bytes.
+    /// Returns `None` if the contents don't look like any format we know.
+    pub fn sniff(contents: &[u8]) -> Option<Self> {
+        const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+        const JPEG_MAGIC: &[u8] = b"\xff\xd8\xff";
+
+        if contents.starts_with(PNG_MAGIC) {
+            Some(FileFormat::Png)
+        } else if contents.starts_with(JPEG_MAGIC) {
+            Some(FileFormat::Jpeg)
+        } else {
+            None
+        }
+    }
+
+    /// Guesses the format of an image from its path's file extension.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "png" => Some(FileFormat::Png),
+            "jpg" | "jpeg" => Some(FileFormat::Jpeg),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileFormat::Png => formatter.write_str("PNG"),
+            FileFormat::Jpeg => formatter.write_str("JPEG"),
+        }
+    }
+}
+
+/// How hard to try to make encoded PNG images small. Smaller images take
+/// longer to encode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PngCompression {
+    /// Encode quickly, at the cost of larger files.
+    Fast,
+
+    /// The png crate's defaults.
+    #[default]
+    Default,
+
+    /// Spend more time encoding to make files smaller.
+    Best,
+}
+
+impl PngCompression {
+    fn level(self) -> png::Compression {
+        match self {
+            PngCompression::Fast => png::Compression::Fast,
+            PngCompression::Default => png::Compression::Default,
+            PngCompression::Best => png::Compression::Best,
+        }
+    }
+}
+
+/// How `Image::resize` picks the color of each pixel in the resized image.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResizeFilter {
+    /// Use the closest source pixel. Keeps pixel art crisp, and produces
+    /// exactly the source's pixels when scaling by whole numbers.
+    Nearest,
+
+    /// Blend the four closest source pixels. Smooths out gradients and
+    /// photographic images.
+    #[default]
+    Bilinear,
+}
+
+/// Converts a color channel stored as sRGB to linear light between 0 and 1.
+///
+/// Images are treated as sRGB, which is how PNG and JPEG files are almost
+/// always authored. Averaging sRGB values directly darkens the blend between
+/// bright and dark colors, so operations that mix colors can convert them to
+/// linear light first.
+pub(crate) fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts linear light between 0 and 1 to a color channel stored as sRGB.
+pub(crate) fn linear_to_srgb(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+
+    let encoded = if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+
+    (encoded * 255.0).round() as u8
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct Pixel {
     pub r: u8,
@@ -29,7 +154,69 @@ impl Pixel {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Parses colors written as `r,g,b` or `r,g,b,a`, like `255,0,255`. Alpha
+/// defaults to fully opaque.
+impl FromStr for Pixel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid color '{}', expected r,g,b or r,g,b,a", value);
+
+        let components = value
+            .split(',')
+            .map(|component| component.trim().parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+
+        match *components.as_slice() {
+            [r, g, b] => Ok(Pixel::new(r, g, b, 255)),
+            [r, g, b, a] => Ok(Pixel::new(r, g, b, a)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error(
+    "Can't crop a {}x{} region at ({}, {}) out of a {}x{} image",
+    .size.0,
+    .size.1,
+    .pos.0,
+    .pos.1,
+    .image_size.0,
+    .image_size.1
+)]
+pub(crate) struct CropError {
+    pos: (u32, u32),
+    size: (u32, u32),
+    image_size: (u32, u32),
+}
+
+/// Why the size of an encoded image couldn't be read from its header.
+#[derive(Debug, Error)]
+pub(crate) enum ReadDimensionsError {
+    #[error("The image is not a PNG or JPEG image")]
+    UnknownFormat,
+
+    #[error("The JPEG image has no frame header")]
+    MissingJpegFrame,
+
+    #[error(transparent)]
+    Png {
+        #[from]
+        source: png::DecodingError,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+/// Images are equal when they have the same size, format, and pixels, no matter
+/// how they were encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Image {
     size: (u32, u32),
     data: Vec<u8>,
@@ -38,10 +225,22 @@ pub(crate) struct Image {
 
 impl Image {
     pub fn new_rgba8<D: Into<Vec<u8>>>(size: (u32, u32), data: D) -> Self {
-        let data = data.into();
+        Self::from_rgba8_parts(size, data.into())
+    }
+
+    /// Creates an image that takes ownership of an existing buffer of RGBA8
+    /// pixel data, without copying or reallocating it.
+    ///
+    /// Panics if `data` is not exactly the right length for `size`.
+    pub fn from_rgba8_parts(size: (u32, u32), data: Vec<u8>) -> Self {
         let format = ImageFormat::Rgba8;
 
-        assert!(data.len() == (size.0 * size.1 * format.stride()) as usize);
+        assert_eq!(
+            data.len(),
+            (size.0 * size.1 * format.stride()) as usize,
+            "RGBA8 image data had the wrong length for an image of size {:?}",
+            size
+        );
 
         Self { size, data, format }
     }
@@ -51,6 +250,12 @@ impl Image {
         Self::new_rgba8(size, data)
     }
 
+    /// Creates an image where every pixel is the given color.
+    pub fn new_filled_rgba8(size: (u32, u32), fill: Pixel) -> Self {
+        let data = [fill.r, fill.g, fill.b, fill.a].repeat((size.0 * size.1) as usize);
+        Self::new_rgba8(size, data)
+    }
+
     pub fn decode_png<R: Read>(input: R) -> Result<Self, png::DecodingError> {
         let decoder = png::Decoder::new(input);
 
@@ -74,37 +279,166 @@ impl Image {
 
         let size = (info.width, info.height);
 
-        Ok(Self::new_rgba8(size, data))
+        Ok(Self::from_rgba8_parts(size, data))
     }
 
-    pub fn encode_png<W: Write>(&self, output: W) -> Result<(), png::EncodingError> {
-        let mut encoder = png::Encoder::new(output, self.size.0, self.size.1);
+    /// Reads only the header of a PNG or JPEG image to find its size, without
+    /// decoding any of its pixel data.
+    pub fn read_dimensions<R: Read>(mut input: R) -> Result<(u32, u32), ReadDimensionsError> {
+        let mut magic = [0; 8];
+        input.read_exact(&mut magic)?;
 
-        match self.format {
-            ImageFormat::Rgba8 => {
-                encoder.set_color(png::ColorType::RGBA);
-                encoder.set_depth(png::BitDepth::Eight);
+        match FileFormat::sniff(&magic) {
+            Some(FileFormat::Png) => {
+                let decoder = png::Decoder::new(magic.chain(input));
+                let (info, _reader) = decoder.read_info()?;
+
+                Ok((info.width, info.height))
             }
+            Some(FileFormat::Jpeg) => {
+                // The start of image marker is the first two bytes of the
+                // magic, so the first segment starts right after it.
+                read_jpeg_dimensions(magic[2..].chain(input))
+            }
+            None => Err(ReadDimensionsError::UnknownFormat),
         }
+    }
+
+    pub fn encode_png_with_compression<W: Write>(
+        &self,
+        output: W,
+        compression: PngCompression,
+    ) -> Result<(), png::EncodingError> {
+        self.encode_png_with_options(output, compression, false)
+    }
+
+    /// Encodes this image as a PNG. If `optimize_colors` is set, the PNG drops
+    /// any channels that the image doesn't need: alpha if every pixel is fully
+    /// opaque, and color if every pixel is a shade of gray.
+    pub fn encode_png_with_options<W: Write>(
+        &self,
+        output: W,
+        compression: PngCompression,
+        optimize_colors: bool,
+    ) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(output, self.size.0, self.size.1);
+
+        encoder.set_compression(compression.level());
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let color_type = match self.format {
+            ImageFormat::Rgba8 if optimize_colors => self.smallest_color_type(),
+            ImageFormat::Rgba8 => png::ColorType::RGBA,
+        };
+        encoder.set_color(color_type);
 
         let mut output_writer = encoder.write_header()?;
-        output_writer.write_image_data(&self.data)?;
+
+        if color_type == png::ColorType::RGBA {
+            output_writer.write_image_data(self.data())?;
+        } else {
+            let data: Vec<u8> = self
+                .data
+                .chunks_exact(4)
+                .flat_map(|pixel| {
+                    let channels: &[u8] = match color_type {
+                        png::ColorType::Grayscale => &pixel[..1],
+                        png::ColorType::GrayscaleAlpha => &[pixel[0], pixel[3]],
+                        _ => &pixel[..3],
+                    };
+                    channels.to_vec()
+                })
+                .collect();
+
+            output_writer.write_image_data(&data)?;
+        }
 
         // On drop, output_writer will write the last chunk of the PNG file.
         Ok(())
     }
 
+    /// The smallest 8-bit PNG color type that can hold every pixel of this
+    /// image without losing anything.
+    fn smallest_color_type(&self) -> png::ColorType {
+        let opaque = self.is_fully_opaque();
+        let gray = self
+            .data
+            .chunks_exact(4)
+            .all(|pixel| pixel[0] == pixel[1] && pixel[1] == pixel[2]);
+
+        match (opaque, gray) {
+            (true, true) => png::ColorType::Grayscale,
+            (true, false) => png::ColorType::RGB,
+            (false, true) => png::ColorType::GrayscaleAlpha,
+            (false, false) => png::ColorType::RGBA,
+        }
+    }
+
     pub fn size(&self) -> (u32, u32) {
         self.size
     }
 
-    pub fn blit(&mut self, other: &Image, pos: (u32, u32)) {
+    /// Hashes everything that makes images equal: their size, format, and raw
+    /// pixels. Like comparing images, this doesn't depend on how the image was
+    /// encoded.
+    pub fn pixel_hash(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+
+        hasher.update(&self.size.0.to_le_bytes());
+        hasher.update(&self.size.1.to_le_bytes());
+        hasher.update(&[self.format.stride() as u8]);
+        hasher.update(&self.data);
+
+        format!("{}", hasher.finalize().to_hex())
+    }
+
+    /// The raw pixel data of this image, stored row by row.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Iterates over every pixel in this image along with its position, row by
+    /// row. This is much cheaper than calling `get_pixel` for every position.
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32, Pixel)> + '_ {
+        let width = self.size.0;
+
+        self.data
+            .chunks_exact(self.format.stride() as usize)
+            .enumerate()
+            .map(move |(index, pixel)| {
+                let index = index as u32;
+                let pixel = Pixel::new(pixel[0], pixel[1], pixel[2], pixel[3]);
+
+                (index % width, index / width, pixel)
+            })
+    }
+
+    /// Like `pixels`, but gives mutable access to each pixel's RGBA bytes.
+    pub fn pixels_mut(&mut self) -> impl Iterator<Item = (u32, u32, &mut [u8; 4])> + '_ {
+        let width = self.size.0;
+
+        self.data
+            .chunks_exact_mut(self.format.stride() as usize)
+            .enumerate()
+            .map(move |(index, pixel)| {
+                let index = index as u32;
+                let pixel = <&mut [u8; 4]>::try_from(pixel).unwrap();
+
+                (index % width, index / width, pixel)
+            })
+    }
+
+    /// Copies another image into this one with its top-left corner at `pos`.
+    ///
+    /// The source can be an `&Image` or an `ImageView` over borrowed pixels.
+    pub fn blit<'a, I: Into<ImageView<'a>>>(&mut self, other: I, pos: (u32, u32)) {
+        let other = other.into();
         assert!(self.format == ImageFormat::Rgba8 && other.format == ImageFormat::Rgba8);
 
         let stride = self.format.stride();
 
-        let other_width_bytes = other.size.0 * stride;
-        let other_rows = other.data.chunks_exact((other_width_bytes) as usize);
+        let other_width_bytes = other.size().0 * stride;
+        let other_rows = other.data().chunks_exact((other_width_bytes) as usize);
 
         for (other_y, other_row) in other_rows.enumerate() {
             let self_y = pos.1 + other_y as u32;
@@ -119,6 +453,152 @@ impl Image {
         }
     }
 
+    /// Whether every pixel in this image is fully opaque. Stops at the first
+    /// pixel that isn't.
+    pub fn is_fully_opaque(&self) -> bool {
+        self.alphas().all(|alpha| alpha == 255)
+    }
+
+    /// Whether every pixel in this image is fully transparent. Stops at the
+    /// first pixel that isn't.
+    pub fn is_fully_transparent(&self) -> bool {
+        self.alphas().all(|alpha| alpha == 0)
+    }
+
+    fn alphas(&self) -> impl Iterator<Item = u8> + '_ {
+        match self.format {
+            ImageFormat::Rgba8 => self.data.chunks_exact(4).map(|pixel| pixel[3]),
+        }
+    }
+
+    /// Finds the smallest rectangle that contains every pixel that isn't fully
+    /// transparent, returned as its position and size. Returns `None` if the
+    /// whole image is transparent.
+    pub fn opaque_bounds(&self) -> Option<((u32, u32), (u32, u32))> {
+        let mut min = (u32::MAX, u32::MAX);
+        let mut max = (0, 0);
+
+        for (x, y, pixel) in self.pixels() {
+            if pixel.a != 0 {
+                min = (min.0.min(x), min.1.min(y));
+                max = (max.0.max(x + 1), max.1.max(y + 1));
+            }
+        }
+
+        if min.0 > max.0 {
+            None
+        } else {
+            Some((min, (max.0 - min.0, max.1 - min.1)))
+        }
+    }
+
+    /// Copies the given region of this image into a new image. Fails if any
+    /// part of the region is outside of this image.
+    pub fn crop(&self, pos: (u32, u32), size: (u32, u32)) -> Result<Image, CropError> {
+        let in_bounds = |start: u32, length: u32, bound: u32| {
+            start.checked_add(length).is_some_and(|end| end <= bound)
+        };
+
+        if !in_bounds(pos.0, size.0, self.size.0) || !in_bounds(pos.1, size.1, self.size.1) {
+            return Err(CropError {
+                pos,
+                size,
+                image_size: self.size,
+            });
+        }
+
+        let stride = self.format.stride() as usize;
+        let mut data = Vec::with_capacity(size.0 as usize * size.1 as usize * stride);
+
+        for y in pos.1..pos.1 + size.1 {
+            let start = stride * (pos.0 + y * self.size.0) as usize;
+            let end = start + stride * size.0 as usize;
+
+            data.extend_from_slice(&self.data[start..end]);
+        }
+
+        Ok(Self::from_rgba8_parts(size, data))
+    }
+
+    /// Creates a copy of this image scaled to the given size.
+    ///
+    /// Pixels are sampled at their centers, so downscaling by 2x with
+    /// `Bilinear` averages each 2x2 block. Colors are blended with
+    /// premultiplied alpha so that transparent pixels don't darken the edges
+    /// of opaque ones.
+    pub fn resize(&self, size: (u32, u32), filter: ResizeFilter) -> Image {
+        let mut resized = Image::new_empty_rgba8(size);
+
+        if self.size.0 == 0 || self.size.1 == 0 {
+            return resized;
+        }
+
+        let scale_x = self.size.0 as f32 / size.0 as f32;
+        let scale_y = self.size.1 as f32 / size.1 as f32;
+
+        for (x, y, resized_pixel) in resized.pixels_mut() {
+            let source_x = (x as f32 + 0.5) * scale_x;
+            let source_y = (y as f32 + 0.5) * scale_y;
+
+            let pixel = match filter {
+                ResizeFilter::Nearest => self.get_pixel((
+                    (source_x as u32).min(self.size.0 - 1),
+                    (source_y as u32).min(self.size.1 - 1),
+                )),
+                ResizeFilter::Bilinear => self.sample_bilinear(source_x - 0.5, source_y - 0.5),
+            };
+
+            *resized_pixel = [pixel.r, pixel.g, pixel.b, pixel.a];
+        }
+
+        resized
+    }
+
+    /// Blends the four pixels around the given position, where whole numbers
+    /// are the centers of pixels.
+    fn sample_bilinear(&self, x: f32, y: f32) -> Pixel {
+        let max_x = (self.size.0 - 1) as f32;
+        let max_y = (self.size.1 - 1) as f32;
+        let x = x.clamp(0.0, max_x);
+        let y = y.clamp(0.0, max_y);
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (x1, y1) = ((x0 + 1.0).min(max_x), (y0 + 1.0).min(max_y));
+        let (fx, fy) = (x - x0, y - y0);
+
+        let samples = [
+            ((x0, y0), (1.0 - fx) * (1.0 - fy)),
+            ((x1, y0), fx * (1.0 - fy)),
+            ((x0, y1), (1.0 - fx) * fy),
+            ((x1, y1), fx * fy),
+        ];
+
+        let mut total = [0.0f32; 4];
+        for &((sample_x, sample_y), weight) in &samples {
+            let pixel = self.get_pixel((sample_x as u32, sample_y as u32));
+            let alpha = pixel.a as f32 / 255.0;
+
+            total[0] += pixel.r as f32 * alpha * weight;
+            total[1] += pixel.g as f32 * alpha * weight;
+            total[2] += pixel.b as f32 * alpha * weight;
+            total[3] += pixel.a as f32 * weight;
+        }
+
+        let alpha = total[3] / 255.0;
+        if alpha == 0.0 {
+            return Pixel::new(0, 0, 0, 0);
+        }
+
+        let channel = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+
+        Pixel::new(
+            channel(total[0] / alpha),
+            channel(total[1] / alpha),
+            channel(total[2] / alpha),
+            channel(total[3]),
+        )
+    }
+
     pub fn get_pixel(&self, pos: (u32, u32)) -> Pixel {
         assert!(pos.0 < self.size.0);
         assert!(pos.1 < self.size.1);
@@ -146,12 +626,477 @@ impl Image {
         self.data[start + 2] = pixel.b;
         self.data[start + 3] = pixel.a;
     }
+
+    /// Draws a one pixel wide outline just inside the given rectangle, given
+    /// as its position and size. Any part of the outline that falls outside of
+    /// the image is skipped.
+    pub fn draw_rect_outline(&mut self, rect: ((u32, u32), (u32, u32)), pixel: Pixel) {
+        let ((x, y), (width, height)) = rect;
+
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let right = x + width - 1;
+        let bottom = y + height - 1;
+
+        for edge_x in x..=right {
+            self.set_pixel_checked((edge_x, y), pixel);
+            self.set_pixel_checked((edge_x, bottom), pixel);
+        }
+
+        for edge_y in y..=bottom {
+            self.set_pixel_checked((x, edge_y), pixel);
+            self.set_pixel_checked((right, edge_y), pixel);
+        }
+    }
+
+    /// Draws a line of text with its top-left corner at `pos`, using a 3x5
+    /// pixel font where each pixel is drawn as a `scale` by `scale` square.
+    /// Any part of the text that falls outside of the image is skipped.
+    pub fn draw_text(&mut self, pos: (u32, u32), text: &str, scale: u32, pixel: Pixel) {
+        for (index, character) in text.chars().enumerate() {
+            let glyph_x = pos.0 + index as u32 * (GLYPH_WIDTH + 1) * scale;
+
+            for (row_index, row) in bitmap_font::glyph(character).iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if row & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                        continue;
+                    }
+
+                    let x = glyph_x + column * scale;
+                    let y = pos.1 + row_index as u32 * scale;
+
+                    for offset_y in 0..scale {
+                        for offset_x in 0..scale {
+                            self.set_pixel_checked((x + offset_x, y + offset_y), pixel);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The size in pixels of a line of text drawn by `draw_text`.
+    pub fn text_size(text: &str, scale: u32) -> (u32, u32) {
+        let length = text.chars().count() as u32;
+        let width = (length * (GLYPH_WIDTH + 1)).saturating_sub(1);
+
+        (width * scale, GLYPH_HEIGHT * scale)
+    }
+
+    fn set_pixel_checked(&mut self, pos: (u32, u32), pixel: Pixel) {
+        if pos.0 < self.size.0 && pos.1 < self.size.1 {
+            self.set_pixel(pos, pixel);
+        }
+    }
+}
+
+/// Walks through the segments of a JPEG image until it finds the frame header,
+/// which holds the image's size, skipping over everything else.
+fn read_jpeg_dimensions<R: Read>(mut input: R) -> Result<(u32, u32), ReadDimensionsError> {
+    fn read_u16<R: Read>(input: &mut R) -> io::Result<u16> {
+        let mut bytes = [0; 2];
+        input.read_exact(&mut bytes)?;
+        Ok(u16::from_be_bytes(bytes))
+    }
+
+    loop {
+        let mut byte = [0; 1];
+
+        // Markers start with 0xFF, which can be repeated as padding.
+        input.read_exact(&mut byte)?;
+        if byte[0] != 0xff {
+            return Err(ReadDimensionsError::MissingJpegFrame);
+        }
+
+        let mut marker = 0xff;
+        while marker == 0xff {
+            input.read_exact(&mut byte)?;
+            marker = byte[0];
+        }
+
+        match marker {
+            // Markers that aren't followed by a segment.
+            0x01 | 0xd0..=0xd7 => continue,
+
+            // The start of the compressed data or the end of the image, which
+            // should never come before the frame header.
+            0xda | 0xd9 => return Err(ReadDimensionsError::MissingJpegFrame),
+
+            // Start of frame markers, except for the ones between them that
+            // mean something else.
+            0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                let _length = read_u16(&mut input)?;
+                let mut precision = [0; 1];
+                input.read_exact(&mut precision)?;
+
+                let height = read_u16(&mut input)?;
+                let width = read_u16(&mut input)?;
+
+                return Ok((width as u32, height as u32));
+            }
+
+            _ => {
+                let length = read_u16(&mut input)?;
+                let skipped = u64::from(length.saturating_sub(2));
+
+                io::copy(&mut (&mut input).take(skipped), &mut io::sink())?;
+            }
+        }
+    }
+}
+
+/// A read-only view over pixel data that is owned by someone else.
+///
+/// This is useful for working with pixels that already live in another buffer,
+/// like procedurally generated sprites, without copying them into an `Image`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ImageView<'a> {
+    size: (u32, u32),
+    data: &'a [u8],
+    format: ImageFormat,
+}
+
+impl<'a> ImageView<'a> {
+    /// Panics if `data` is not exactly the right length for `size`.
+    pub fn new_rgba8(size: (u32, u32), data: &'a [u8]) -> Self {
+        let format = ImageFormat::Rgba8;
+
+        assert_eq!(
+            data.len(),
+            (size.0 * size.1 * format.stride()) as usize,
+            "RGBA8 image data had the wrong length for an image of size {:?}",
+            size
+        );
+
+        Self { size, data, format }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> From<&'a Image> for ImageView<'a> {
+    fn from(image: &'a Image) -> Self {
+        Self::new_rgba8(image.size, &image.data)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn images_compare_by_pixels() {
+        let red = Pixel::new(255, 0, 0, 255);
+
+        let filled = Image::new_filled_rgba8((3, 2), red);
+        let mut drawn = Image::new_empty_rgba8((3, 2));
+        for (_, _, pixel) in drawn.pixels_mut() {
+            *pixel = [255, 0, 0, 255];
+        }
+
+        assert_eq!(filled, drawn);
+        assert_eq!(filled.pixel_hash(), drawn.pixel_hash());
+
+        drawn.set_pixel((2, 1), Pixel::new(255, 0, 0, 254));
+
+        assert_ne!(filled, drawn);
+        assert_ne!(filled.pixel_hash(), drawn.pixel_hash());
+    }
+
+    #[test]
+    fn images_with_the_same_pixels_and_different_sizes_are_not_equal() {
+        let wide = Image::new_empty_rgba8((4, 1));
+        let tall = Image::new_empty_rgba8((1, 4));
+
+        assert_eq!(wide.data(), tall.data());
+        assert_ne!(wide, tall);
+        assert_ne!(wide.pixel_hash(), tall.pixel_hash());
+    }
+
+    #[test]
+    fn decoded_image_equals_original() {
+        let mut image = Image::new_empty_rgba8((5, 4));
+        image.set_pixel((1, 2), Pixel::new(10, 20, 30, 40));
+
+        let mut encoded = Vec::new();
+        image
+            .encode_png_with_compression(&mut encoded, PngCompression::Best)
+            .unwrap();
+
+        assert_eq!(Image::decode_png(encoded.as_slice()).unwrap(), image);
+    }
+
+    #[test]
+    fn read_png_dimensions_from_header() {
+        let mut encoded = Vec::new();
+        Image::new_empty_rgba8((37, 19))
+            .encode_png_with_compression(&mut encoded, PngCompression::Default)
+            .unwrap();
+
+        // Only the signature, the header chunk, and the start of the first data
+        // chunk are kept, so the pixel data can't have been decoded.
+        encoded.truncate(8 + 25 + 8);
+
+        assert_eq!(
+            Image::read_dimensions(encoded.as_slice()).unwrap(),
+            (37, 19)
+        );
+    }
+
+    #[test]
+    fn read_jpeg_dimensions_from_header() {
+        let jpeg: &[u8] = &[
+            0xff, 0xd8, // Start of image
+            0xff, 0xe0, 0x00, 0x06, b'J', b'F', b'I', b'F', // Application data
+            0xff, 0xc0, 0x00, 0x11, 0x08, 0x01, 0x2c, 0x02, 0x80, // Frame header
+        ];
+
+        assert_eq!(Image::read_dimensions(jpeg).unwrap(), (640, 300));
+        assert!(matches!(
+            Image::read_dimensions(&b"GIF89a\0\0"[..]),
+            Err(ReadDimensionsError::UnknownFormat)
+        ));
+    }
+
+    #[test]
+    fn draw_rect_outline() {
+        let red = Pixel::new(255, 0, 0, 255);
+        let mut image = Image::new_empty_rgba8((5, 5));
+
+        image.draw_rect_outline(((1, 1), (3, 3)), red);
+
+        for y in 0..5 {
+            for x in 0..5 {
+                let on_outline = (1..=3).contains(&x)
+                    && (1..=3).contains(&y)
+                    && (x == 1 || x == 3 || y == 1 || y == 3);
+
+                let expected = if on_outline {
+                    red
+                } else {
+                    Pixel::new(0, 0, 0, 0)
+                };
+
+                assert_eq!(image.get_pixel((x, y)), expected, "pixel at {}, {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn draw_rect_outline_out_of_bounds() {
+        let red = Pixel::new(255, 0, 0, 255);
+        let mut image = Image::new_empty_rgba8((4, 4));
+
+        image.draw_rect_outline(((2, 2), (8, 8)), red);
+
+        assert_eq!(image.get_pixel((2, 2)), red);
+        assert_eq!(image.get_pixel((3, 2)), red);
+        assert_eq!(image.get_pixel((3, 3)), Pixel::new(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn draw_text() {
+        let white = Pixel::new(255, 255, 255, 255);
+        let clear = Pixel::new(0, 0, 0, 0);
+        let mut image = Image::new_empty_rgba8(Image::text_size("T1", 2));
+
+        image.draw_text((0, 0), "T1", 2, white);
+
+        // The top bar of the T, then the gap between the two glyphs.
+        assert_eq!(image.get_pixel((5, 0)), white);
+        assert_eq!(image.get_pixel((5, 2)), clear);
+        assert_eq!(image.get_pixel((6, 0)), clear);
+        assert_eq!(image.get_pixel((8, 9)), white);
+
+        // Text that runs off of the image is cut off instead of panicking.
+        image.draw_text((10, 8), "OVERFLOW", 2, white);
+    }
+
+    fn checkerboard(size: (u32, u32)) -> Image {
+        let mut image = Image::new_empty_rgba8(size);
+
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                let value = ((x * 31 + y * 17) % 256) as u8;
+                image.set_pixel((x, y), Pixel::new(value, 255 - value, x as u8, 255));
+            }
+        }
+
+        image
+    }
+
+    #[test]
+    fn pixels_match_get_pixel() {
+        let mut image = checkerboard((7, 5));
+        for (x, y, pixel) in image.pixels_mut() {
+            pixel[3] = (x * 40 + y * 3) as u8;
+        }
+
+        let mut expected = 0;
+        for y in 0..5 {
+            for x in 0..7 {
+                expected += image.get_pixel((x, y)).a as u32;
+            }
+        }
+
+        let total: u32 = image.pixels().map(|(_, _, pixel)| pixel.a as u32).sum();
+        assert_eq!(total, expected);
+
+        for (x, y, pixel) in image.pixels() {
+            assert_eq!(pixel, image.get_pixel((x, y)));
+        }
+    }
+
+    #[test]
+    fn resize_nearest_integer_downscale() {
+        let source = checkerboard((8, 6));
+        let resized = source.resize((4, 3), ResizeFilter::Nearest);
+
+        for y in 0..3 {
+            for x in 0..4 {
+                assert_eq!(
+                    resized.get_pixel((x, y)),
+                    source.get_pixel((x * 2 + 1, y * 2 + 1))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn resize_nearest_integer_upscale() {
+        let source = checkerboard((3, 2));
+        let resized = source.resize((9, 6), ResizeFilter::Nearest);
+
+        for y in 0..6 {
+            for x in 0..9 {
+                assert_eq!(resized.get_pixel((x, y)), source.get_pixel((x / 3, y / 3)));
+            }
+        }
+    }
+
+    #[test]
+    fn resize_bilinear_averages_blocks() {
+        let mut source = Image::new_empty_rgba8((2, 2));
+        source.set_pixel((0, 0), Pixel::new(0, 0, 0, 255));
+        source.set_pixel((1, 0), Pixel::new(100, 0, 0, 255));
+        source.set_pixel((0, 1), Pixel::new(0, 200, 0, 255));
+        source.set_pixel((1, 1), Pixel::new(100, 200, 0, 255));
+
+        let resized = source.resize((1, 1), ResizeFilter::Bilinear);
+
+        assert_eq!(resized.get_pixel((0, 0)), Pixel::new(50, 100, 0, 255));
+    }
+
+    #[test]
+    fn resize_bilinear_ignores_transparent_colors() {
+        let mut source = Image::new_empty_rgba8((2, 1));
+        source.set_pixel((0, 0), Pixel::new(255, 255, 255, 255));
+        source.set_pixel((1, 0), Pixel::new(0, 0, 0, 0));
+
+        let resized = source.resize((1, 1), ResizeFilter::Bilinear);
+
+        assert_eq!(resized.get_pixel((0, 0)), Pixel::new(255, 255, 255, 128));
+    }
+
+    #[test]
+    fn compression_levels_round_trip() {
+        let image = checkerboard((32, 32));
+
+        for &compression in &[
+            PngCompression::Fast,
+            PngCompression::Default,
+            PngCompression::Best,
+        ] {
+            let mut encoded = Vec::new();
+            image
+                .encode_png_with_compression(&mut encoded, compression)
+                .unwrap();
+
+            let decoded = Image::decode_png(encoded.as_slice()).unwrap();
+            assert_eq!(decoded.data(), image.data(), "{:?}", compression);
+        }
+    }
+
+    /// Decodes a PNG without converting it, returning its color type and
+    /// pixel data.
+    fn decode_raw_png(encoded: &[u8]) -> (png::ColorType, Vec<u8>) {
+        let (info, mut reader) = png::Decoder::new(encoded).read_info().unwrap();
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+
+        (info.color_type, data)
+    }
+
+    #[test]
+    fn optimized_color_types() {
+        let cases = [
+            (
+                Pixel::new(10, 10, 10, 255),
+                png::ColorType::Grayscale,
+                vec![10],
+            ),
+            (
+                Pixel::new(10, 20, 30, 255),
+                png::ColorType::RGB,
+                vec![10, 20, 30],
+            ),
+            (
+                Pixel::new(10, 10, 10, 128),
+                png::ColorType::GrayscaleAlpha,
+                vec![10, 128],
+            ),
+            (
+                Pixel::new(10, 20, 30, 128),
+                png::ColorType::RGBA,
+                vec![10, 20, 30, 128],
+            ),
+        ];
+
+        for (fill, color_type, channels) in cases.iter() {
+            let image = Image::new_filled_rgba8((2, 2), *fill);
+
+            let mut encoded = Vec::new();
+            image
+                .encode_png_with_options(&mut encoded, PngCompression::Default, true)
+                .unwrap();
+
+            assert_eq!(
+                decode_raw_png(&encoded),
+                (*color_type, channels.repeat(4)),
+                "{:?}",
+                fill
+            );
+        }
+    }
+
+    #[test]
+    fn unoptimized_color_type_is_rgba() {
+        let image = Image::new_filled_rgba8((2, 2), Pixel::new(10, 10, 10, 255));
+
+        let mut encoded = Vec::new();
+        image
+            .encode_png_with_options(&mut encoded, PngCompression::Default, false)
+            .unwrap();
+
+        assert_eq!(decode_raw_png(&encoded).0, png::ColorType::RGBA);
+    }
+
+    #[test]
+    fn parse_pixel() {
+        assert_eq!("255,0,255".parse(), Ok(Pixel::new(255, 0, 255, 255)));
+        assert_eq!("1, 2, 3, 4".parse(), Ok(Pixel::new(1, 2, 3, 4)));
+        assert!("1,2".parse::<Pixel>().is_err());
+        assert!("red".parse::<Pixel>().is_err());
+    }
+
     #[test]
     fn blit_zero() {
         let source = Image::new_empty_rgba8((17, 20));
@@ -168,6 +1113,95 @@ mod test {
         target.blit(&source, (4, 4));
     }
 
+    #[test]
+    fn from_rgba8_parts_does_not_copy() {
+        let data = vec![0; 2 * 2 * 4];
+        let data_ptr = data.as_ptr();
+
+        let image = Image::from_rgba8_parts((2, 2), data);
+
+        assert_eq!(image.data().as_ptr(), data_ptr);
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_rgba8_parts_wrong_length() {
+        Image::from_rgba8_parts((2, 2), vec![0; 3]);
+    }
+
+    #[test]
+    fn blit_view() {
+        let pixels = [1, 2, 3, 4, 5, 6, 7, 8];
+        let source = ImageView::new_rgba8((1, 2), &pixels);
+        let mut target = Image::new_empty_rgba8((2, 2));
+
+        target.blit(source, (1, 0));
+
+        assert_eq!(target.get_pixel((0, 0)), Pixel::new(0, 0, 0, 0));
+        assert_eq!(target.get_pixel((1, 0)), Pixel::new(1, 2, 3, 4));
+        assert_eq!(target.get_pixel((1, 1)), Pixel::new(5, 6, 7, 8));
+    }
+
+    #[test]
+    fn srgb_round_trip() {
+        for value in 0..=255 {
+            assert_eq!(linear_to_srgb(srgb_to_linear(value)), value);
+        }
+    }
+
+    #[test]
+    fn opacity_checks() {
+        let opaque = Image::new_filled_rgba8((4, 4), Pixel::new(1, 2, 3, 255));
+        assert!(opaque.is_fully_opaque());
+        assert!(!opaque.is_fully_transparent());
+
+        let transparent = Image::new_empty_rgba8((4, 4));
+        assert!(!transparent.is_fully_opaque());
+        assert!(transparent.is_fully_transparent());
+
+        let mut mixed = opaque.clone();
+        mixed.set_pixel((3, 3), Pixel::new(1, 2, 3, 128));
+        assert!(!mixed.is_fully_opaque());
+        assert!(!mixed.is_fully_transparent());
+    }
+
+    #[test]
+    fn opaque_bounds() {
+        let mut image = Image::new_empty_rgba8((5, 4));
+        assert_eq!(image.opaque_bounds(), None);
+
+        image.set_pixel((1, 2), Pixel::new(0, 0, 0, 1));
+        assert_eq!(image.opaque_bounds(), Some(((1, 2), (1, 1))));
+
+        image.set_pixel((3, 1), Pixel::new(0, 0, 0, 1));
+        assert_eq!(image.opaque_bounds(), Some(((1, 1), (3, 2))));
+    }
+
+    #[test]
+    fn crop() {
+        let mut image = Image::new_empty_rgba8((4, 4));
+        image.set_pixel((1, 2), Pixel::new(1, 2, 3, 4));
+        image.set_pixel((2, 2), Pixel::new(5, 6, 7, 8));
+
+        let cropped = image.crop((1, 2), (2, 1)).unwrap();
+
+        assert_eq!(cropped.size(), (2, 1));
+        assert_eq!(cropped.data(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let whole = image.crop((0, 0), (4, 4)).unwrap();
+        assert_eq!(whole.data(), image.data());
+    }
+
+    #[test]
+    fn crop_out_of_bounds() {
+        let image = Image::new_empty_rgba8((4, 4));
+
+        assert!(image.crop((3, 0), (2, 1)).is_err());
+        assert!(image.crop((0, 3), (1, 2)).is_err());
+        assert!(image.crop((5, 5), (0, 0)).is_err());
+        assert!(image.crop((1, 1), (u32::MAX, 1)).is_err());
+    }
+
     #[test]
     fn get_pixel() {
         let source = Image::new_empty_rgba8((4, 4));
@@ -176,6 +1210,15 @@ mod test {
         assert_eq!(source.get_pixel((3, 3)), Pixel::new(0, 0, 0, 0));
     }
 
+    #[test]
+    fn new_filled_rgba8() {
+        let fill = Pixel::new(255, 0, 255, 255);
+        let source = Image::new_filled_rgba8((3, 2), fill);
+
+        assert_eq!(source.get_pixel((0, 0)), fill);
+        assert_eq!(source.get_pixel((2, 1)), fill);
+    }
+
     #[test]
     fn set_pixel() {
         let mut source = Image::new_empty_rgba8((3, 3));
@@ -189,4 +1232,35 @@ mod test {
         assert_eq!(&source.data[0..4], &[1, 2, 3, 4]);
         assert_eq!(&source.data[(source.data.len() - 4)..], &[5, 6, 7, 8]);
     }
+
+    #[test]
+    fn sniff_file_format() {
+        let mut encoded = Vec::new();
+        Image::new_empty_rgba8((2, 2))
+            .encode_png_with_compression(&mut encoded, PngCompression::Default)
+            .unwrap();
+
+        assert_eq!(FileFormat::sniff(&encoded), Some(FileFormat::Png));
+        assert_eq!(
+            FileFormat::sniff(b"\xff\xd8\xff\xe0"),
+            Some(FileFormat::Jpeg)
+        );
+        assert_eq!(FileFormat::sniff(b"<!DOCTYPE html>"), None);
+        assert_eq!(FileFormat::sniff(&encoded[..4]), None);
+        assert_eq!(FileFormat::sniff(&[]), None);
+    }
+
+    #[test]
+    fn file_format_from_extension() {
+        assert_eq!(
+            FileFormat::from_extension(Path::new("foo/bar.png")),
+            Some(FileFormat::Png)
+        );
+        assert_eq!(
+            FileFormat::from_extension(Path::new("bar.jpg")),
+            Some(FileFormat::Jpeg)
+        );
+        assert_eq!(FileFormat::from_extension(Path::new("bar.psd")), None);
+        assert_eq!(FileFormat::from_extension(Path::new("bar")), None);
+    }
 }
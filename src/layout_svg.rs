@@ -0,0 +1,101 @@
+//! Draws the layout of a packed spritesheet as an SVG, with a labeled
+//! rectangle for each image in it. Useful for reviewing how changes to packing
+//! affect a spritesheet without looking at its pixels.
+
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{asset_name::AssetName, data::ImageSlice};
+
+pub(crate) fn layout_svg(size: (u32, u32), slices: &HashMap<AssetName, ImageSlice>) -> String {
+    let (width, height) = size;
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r##"  <rect width="{}" height="{}" fill="#eeeeee"/>"##,
+        width, height
+    )
+    .unwrap();
+
+    // Sorting keeps the output stable, so layouts can be diffed between runs.
+    let mut slices: Vec<_> = slices.iter().collect();
+    slices.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (name, slice) in slices {
+        let (x, y) = slice.min();
+        let (w, h) = slice.size();
+        let name = escape_xml(name.as_ref());
+
+        writeln!(svg, "  <g>").unwrap();
+        writeln!(svg, "    <title>{}</title>", name).unwrap();
+        writeln!(
+            svg,
+            r##"    <rect x="{}" y="{}" width="{}" height="{}" fill="#ffffff" stroke="#ff00ff" stroke-width="1"/>"##,
+            x, y, w, h
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"    <text x="{}" y="{}" font-family="sans-serif" font-size="8" dominant-baseline="hanging">{}</text>"#,
+            x + 1,
+            y + 1,
+            name
+        )
+        .unwrap();
+        writeln!(svg, "  </g>").unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn one_rect_per_slice() {
+        let mut slices = HashMap::new();
+        slices.insert(AssetName::new("b.png"), ImageSlice::new((10, 0), (20, 5)));
+        slices.insert(
+            AssetName::new("a&<b>.png"),
+            ImageSlice::new((0, 0), (10, 10)),
+        );
+
+        let svg = layout_svg((32, 16), &slices);
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(r#"width="32" height="16""#));
+        assert!(svg.contains(r#"<rect x="0" y="0" width="10" height="10""#));
+        assert!(svg.contains(r#"<rect x="10" y="0" width="10" height="5""#));
+        assert!(svg.contains("<title>a&amp;&lt;b&gt;.png</title>"));
+
+        // Slices are written in name order.
+        assert!(svg.find("a&amp;").unwrap() < svg.find("b.png").unwrap());
+    }
+}
@@ -7,6 +7,9 @@
 
 use std::fmt::{self, Write};
 
+#[cfg(test)]
+pub(crate) mod syntax;
+
 /// Trait that helps turn a type into an equivalent Lua snippet.
 ///
 /// Designed to be similar to the `Display` trait from Rust's std.
@@ -129,6 +132,9 @@ pub(crate) enum Expression {
     Table(Table),
     Function(Function),
 
+    /// A call to `require` with the given expression as the module to load.
+    Require(Box<Expression>),
+
     /// Used as a catch-all for when this module doesn't define a primitive we
     /// need for codegen.
     Raw(String),
@@ -136,7 +142,43 @@ pub(crate) enum Expression {
 
 impl Expression {
     pub fn table(entries: Vec<(Expression, Expression)>) -> Self {
-        Self::Table(Table { entries })
+        Self::Table(Table {
+            items: Vec::new(),
+            entries,
+        })
+    }
+
+    /// Creates an array-like table, like `{1, 2, 3}`.
+    pub fn array(items: Vec<Expression>) -> Self {
+        Self::Table(Table {
+            items,
+            entries: Vec::new(),
+        })
+    }
+
+    pub fn require<E: Into<Expression>>(module: E) -> Self {
+        Self::Require(Box::new(module.into()))
+    }
+
+    /// Creates an expression that indexes into `base` by each of the given
+    /// names in order, like `script.Parent.Foo["bar-baz"]`.
+    ///
+    /// `base` is emitted as-is, so it should already be valid Lua.
+    pub fn index_path<S: AsRef<str>>(base: &str, names: &[S]) -> Self {
+        let mut path = base.to_owned();
+
+        for name in names {
+            let name = name.as_ref();
+
+            if is_valid_ident(name) {
+                path.push('.');
+                path.push_str(name);
+            } else {
+                path.push_str(&format!("[\"{}\"]", escape_string(name)));
+            }
+        }
+
+        Self::Raw(path)
     }
 }
 
@@ -146,6 +188,11 @@ impl FmtLua for Expression {
             Self::Table(inner) => inner.fmt_lua(output),
             Self::String(inner) => inner.fmt_lua(output),
             Self::Function(inner) => inner.fmt_lua(output),
+            Self::Require(inner) => {
+                write!(output, "require(")?;
+                inner.fmt_lua(output)?;
+                write!(output, ")")
+            }
             Self::Raw(inner) => output.write_str(inner),
         }
     }
@@ -155,6 +202,11 @@ impl FmtLua for Expression {
             Self::Table(inner) => inner.fmt_table_key(output),
             Self::String(inner) => inner.fmt_table_key(output),
             Self::Function(inner) => inner.fmt_table_key(output),
+            Self::Require(_) => {
+                write!(output, "[")?;
+                self.fmt_lua(output)?;
+                write!(output, "]")
+            }
             Self::Raw(inner) => output.write_str(inner),
         }
     }
@@ -186,25 +238,47 @@ impl From<Table> for Expression {
 
 impl FmtLua for String {
     fn fmt_lua(&self, output: &mut LuaStream<'_>) -> fmt::Result {
-        write!(output, "\"{}\"", self)
+        write!(output, "\"{}\"", escape_string(self))
     }
 
     fn fmt_table_key(&self, output: &mut LuaStream<'_>) -> fmt::Result {
         if is_valid_ident(self) {
             write!(output, "{}", self)
         } else {
-            write!(output, "[\"{}\"]", self)
+            write!(output, "[\"{}\"]", escape_string(self))
+        }
+    }
+}
+
+/// Escapes a string so that it can be put between double quotes in Lua.
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_ascii_control() => escaped.push_str(&format!("\\{:03}", c as u32)),
+            c => escaped.push(c),
         }
     }
+
+    escaped
 }
 
 pub(crate) struct Table {
+    /// Values without keys, which Lua numbers starting from 1. These are
+    /// written before any keyed entries.
+    pub items: Vec<Expression>,
     pub entries: Vec<(Expression, Expression)>,
 }
 
 impl Table {
     pub fn new() -> Self {
         Self {
+            items: Vec::new(),
             entries: Vec::new(),
         }
     }
@@ -216,9 +290,28 @@ impl Table {
 
 impl FmtLua for Table {
     fn fmt_lua(&self, output: &mut LuaStream<'_>) -> fmt::Result {
+        // Short lists, like the parts of a rect, read best on one line.
+        if self.entries.is_empty() && !self.items.is_empty() {
+            write!(output, "{{")?;
+
+            for (i, item) in self.items.iter().enumerate() {
+                if i > 0 {
+                    write!(output, ", ")?;
+                }
+                item.fmt_lua(output)?;
+            }
+
+            return write!(output, "}}");
+        }
+
         writeln!(output, "{{")?;
         output.indent();
 
+        for item in &self.items {
+            item.fmt_lua(output)?;
+            writeln!(output, ",")?;
+        }
+
         for (key, value) in &self.entries {
             key.fmt_table_key(output)?;
             write!(output, " = ")?;
@@ -345,3 +438,93 @@ impl<'a> LuaStream<'a> {
         self.inner.write_str("\n")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{syntax::assert_valid_lua, *};
+
+    #[test]
+    fn require_index_path() {
+        let path = Expression::index_path("script.Parent", &["Assets", "ui-icons"]);
+        let ast = Statement::Return(Expression::require(path));
+
+        assert_eq!(
+            ast.to_string(),
+            "return require(script.Parent.Assets[\"ui-icons\"])"
+        );
+    }
+
+    #[test]
+    fn require_in_table() {
+        let mut table = Table::new();
+        table.add_entry(
+            "icons",
+            Expression::require(Expression::index_path("script", &["icons"])),
+        );
+
+        let ast = Statement::Return(table.into());
+
+        assert_eq!(
+            ast.to_string(),
+            "return {\n\ticons = require(script.icons),\n}"
+        );
+    }
+
+    #[test]
+    fn generated_code_is_valid_lua() {
+        let mut nested = Table::new();
+        nested.add_entry("Image", Expression::String("rbxassetid://1".to_owned()));
+        nested.add_entry(
+            "ImageRectSize",
+            Expression::Raw("Vector2.new(16, 16)".to_owned()),
+        );
+
+        let mut table = Table::new();
+        table.add_entry("ui-icons", nested);
+        table.add_entry("close", Expression::String("rbxassetid://2".to_owned()));
+
+        let mut if_block = IfBlock::new(
+            Expression::Raw("dpiScale >= 3".to_owned()),
+            Statement::Return(Expression::String("rbxassetid://3".to_owned())),
+        );
+        if_block.else_if_blocks.push((
+            Expression::Raw("dpiScale >= 2".to_owned()),
+            Statement::Return(table.into()).into(),
+        ));
+        if_block.else_block = Some(Statement::Return(Expression::Raw("nil".to_owned())).into());
+
+        let function = Function::new("dpiScale".to_owned(), vec![Statement::If(if_block)]);
+        let ast = Statement::Return(Expression::Function(function));
+
+        assert_valid_lua(&ast.to_string());
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        let mut table = Table::new();
+        table.add_entry("say \"hi\"", "C:\\icons\n\u{7}");
+        let ast = Statement::Return(table.into());
+
+        assert_eq!(
+            ast.to_string(),
+            "return {\n\t[\"say \\\"hi\\\"\"] = \"C:\\\\icons\\n\\007\",\n}"
+        );
+        assert_valid_lua(&ast.to_string());
+    }
+
+    #[test]
+    fn array_tables() {
+        let rect = Expression::array(vec![
+            Expression::Raw("0".to_owned()),
+            Expression::Raw("8".to_owned()),
+            Expression::Raw("16".to_owned()),
+            Expression::Raw("24".to_owned()),
+        ]);
+
+        let mut table = Table::new();
+        table.add_entry("rect", rect);
+        let ast = Statement::Return(table.into());
+
+        assert_eq!(ast.to_string(), "return {\n\trect = {0, 8, 16, 24},\n}");
+    }
+}
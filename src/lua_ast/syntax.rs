@@ -0,0 +1,43 @@
+//! Checks that generated Lua code actually parses, using full-moon. Only used
+//! by tests.
+
+/// Checks whether the given source is a syntactically valid Lua chunk. Errors
+/// describe the first problem found and where it is.
+pub fn check(source: &str) -> Result<(), String> {
+    full_moon::parse(source)
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// Panics with the generated code and the problem with it if the code isn't
+/// valid Lua.
+pub fn assert_valid_lua(source: &str) {
+    if let Err(err) = check(source) {
+        panic!("Generated code is not valid Lua: {}\n\n{}", err, source);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_lua() {
+        let invalid = [
+            // Unescaped quotes, from names with quotes in them.
+            r#"return { ["a"b"] = 1 }"#,
+            "return \"unfinished\nstring\"",
+            "if x then return 1",
+            "return function(a,) end",
+            "return { a = }",
+            "return { a = 1 } }",
+            "x",
+            "f() = 1",
+            "return 1 +",
+        ];
+
+        for source in &invalid {
+            assert!(check(source).is_err(), "{} should not parse", source);
+        }
+    }
+}
@@ -1,17 +1,22 @@
 mod alpha_bleed;
 mod asset_name;
 mod auth_cookie;
+mod bitmap_font;
+mod cancel;
 mod codegen;
 mod commands;
+mod contact_sheet;
 mod data;
 mod dpi_scale;
 mod glob;
 mod image;
+mod layout_svg;
 mod lua_ast;
 mod options;
 mod roblox_web_api;
 mod roblox_web_api_types;
 mod sync_backend;
+mod timings;
 use std::{env, panic, process};
 
 use backtrace::Backtrace;
@@ -29,6 +34,20 @@ fn run(options: Options) -> Result<(), anyhow::Error> {
             commands::create_cache_map(options.global, sub_options)?
         }
         Subcommand::AssetList(sub_options) => commands::asset_list(options.global, sub_options)?,
+        Subcommand::Codegen(sub_options) => commands::codegen(options.global, sub_options)?,
+        Subcommand::PackPreview(sub_options) => {
+            commands::pack_preview(options.global, sub_options)?
+        }
+        Subcommand::Explain(sub_options) => commands::explain(options.global, sub_options)?,
+        Subcommand::Plan(sub_options) => commands::plan(options.global, sub_options)?,
+        Subcommand::Check(sub_options) => commands::check(options.global, sub_options)?,
+        Subcommand::PopulateCache(sub_options) => {
+            commands::populate_cache(options.global, sub_options)?
+        }
+        Subcommand::Adopt(sub_options) => commands::adopt(options.global, sub_options)?,
+        Subcommand::ContactSheet(sub_options) => {
+            commands::contact_sheet(options.global, sub_options)?
+        }
     }
 
     Ok(())
@@ -84,6 +103,7 @@ fn main() {
     let options = Options::from_args();
 
     let log_filter = match options.global.verbosity {
+        _ if options.global.quiet => "error",
         0 => "info",
         1 => "info,tarmac=debug",
         2 => "info,tarmac=trace",
@@ -56,7 +56,7 @@ pub struct RawOperationStatusResponseCommon {
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModerationResult {
-    moderation_state: String,
+    pub moderation_state: String,
 }
 
 pub struct ImageUploadData<'a> {
@@ -71,6 +71,30 @@ pub struct ImageUploadMetadata {
     pub display_name: String,
     pub description: String,
     pub creation_context: CreationContext,
+
+    /// Images shown for this asset on its catalog and Creator Store pages.
+    /// Left out of the request entirely when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub previews: Vec<AssetPreview>,
+}
+
+/// A preview image for an asset, as Open Cloud expects it. The preview must
+/// itself be an existing Image asset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetPreview {
+    /// The preview asset's path, like `assets/1234`.
+    pub asset: String,
+    pub alt_text: String,
+}
+
+impl AssetPreview {
+    pub fn new(asset_id: u64, alt_text: String) -> Self {
+        Self {
+            asset: format!("assets/{}", asset_id),
+            alt_text,
+        }
+    }
 }
 
 impl ImageUploadMetadata {
@@ -95,14 +119,25 @@ impl ImageUploadMetadata {
             display_name: display_name.to_string(),
             description: description.to_string(),
             creation_context: CreationContext { creator },
+            previews: Vec::new(),
         })
     }
+
+    pub fn with_preview(mut self, preview: Option<AssetPreview>) -> Self {
+        self.previews.extend(preview);
+        self
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct UploadResponse {
     pub asset_id: u64,
+
+    /// The moderation state of the asset once its upload finished, like
+    /// `Approved` or `Reviewing`.
+    #[serde(skip)]
+    pub moderation_state: Option<String>,
 }
 
 /// Internal representation of what the asset upload endpoint returns, before
@@ -111,15 +146,52 @@ pub struct UploadResponse {
 #[serde(rename_all = "camelCase", untagged)]
 pub enum RawUploadResponse {
     #[serde(rename_all = "camelCase")]
-    Success {
-        path: String,
-        operation_id: String,
-        done: bool,
-    },
+    Success { operation_id: String },
     #[serde(rename_all = "camelCase")]
     Error { code: String, message: String },
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyIntrospectRequest {
+    pub api_key: String,
+}
+
+/// What Open Cloud knows about an API key. Only the parts that Tarmac checks
+/// are kept.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyIntrospectResponse {
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub expired: bool,
+    #[serde(default)]
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyScope {
+    pub name: String,
+    #[serde(default)]
+    pub operations: Vec<String>,
+}
+
+impl ApiKeyIntrospectResponse {
+    /// Whether this key has the `asset:write` scope that uploads need. Scopes
+    /// can be reported either split into a name and operations, or whole.
+    pub fn can_write_assets(&self) -> bool {
+        self.scopes.iter().any(|scope| match scope.name.as_str() {
+            "asset" => scope
+                .operations
+                .iter()
+                .any(|operation| operation == "write"),
+            "asset:write" => true,
+            _ => false,
+        })
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum RobloxAuthenticationError {
     #[error("Exactly one of user_id or group_id must be provided")]
@@ -127,3 +199,67 @@ pub enum RobloxAuthenticationError {
     #[error("Exactly one of api_key or auth must be provided")]
     InvalidAuthProvided,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn metadata() -> ImageUploadMetadata {
+        ImageUploadMetadata::new(
+            "Image".to_owned(),
+            "foo".to_owned(),
+            "Uploaded by Tarmac.".to_owned(),
+            Some(1),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn metadata_without_preview() {
+        let json = serde_json::to_value(metadata()).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "assetType": "Image",
+                "displayName": "foo",
+                "description": "Uploaded by Tarmac.",
+                "creationContext": { "creator": { "userId": "1" } },
+            })
+        );
+    }
+
+    #[test]
+    fn api_key_scopes() {
+        let response: ApiKeyIntrospectResponse = serde_json::from_value(serde_json::json!({
+            "name": "ci",
+            "enabled": true,
+            "expired": false,
+            "scopes": [
+                { "name": "universe-datastores.objects", "operations": ["read"] },
+                { "name": "asset", "operations": ["read", "write"] },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(response.enabled, Some(true));
+        assert!(response.can_write_assets());
+
+        let read_only: ApiKeyIntrospectResponse = serde_json::from_value(serde_json::json!({
+            "scopes": [{ "name": "asset", "operations": ["read"] }],
+        }))
+        .unwrap();
+        assert!(!read_only.can_write_assets());
+    }
+
+    #[test]
+    fn metadata_with_preview() {
+        let preview = AssetPreview::new(1234, "A shiny thing".to_owned());
+        let json = serde_json::to_value(metadata().with_preview(Some(preview))).unwrap();
+
+        assert_eq!(
+            json["previews"],
+            serde_json::json!([{ "asset": "assets/1234", "altText": "A shiny thing" }])
+        );
+    }
+}
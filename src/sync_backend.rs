@@ -1,8 +1,9 @@
-use std::{borrow::Cow, io, path::Path, thread, time::Duration};
+use std::{borrow::Cow, io, path::PathBuf, thread, time::Duration};
 
-use crate::roblox_web_api::{RobloxApiClient, RobloxApiError, IMAGE};
+use crate::image::FileFormat;
+use crate::roblox_web_api::{RetryPolicy, RobloxApiClient, RobloxApiError, IMAGE};
 use crate::roblox_web_api_types::{
-    ImageUploadData, ImageUploadMetadata, RobloxAuthenticationError,
+    AssetPreview, ImageUploadData, ImageUploadMetadata, RobloxAuthenticationError,
 };
 use fs_err as fs;
 use reqwest::StatusCode;
@@ -15,6 +16,21 @@ pub trait SyncBackend {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct UploadResponse {
     pub id: u64,
+
+    /// The moderation state reported for the new asset, if the backend has a
+    /// concept of moderation.
+    pub moderation_state: Option<String>,
+}
+
+impl UploadResponse {
+    /// Tells whether the uploaded asset has been approved by moderation.
+    /// Assets from backends without moderation are always approved.
+    pub fn is_approved(&self) -> bool {
+        match &self.moderation_state {
+            Some(state) => state == "Approved" || state == "MODERATION_STATE_APPROVED",
+            None => true,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -22,17 +38,26 @@ pub struct UploadInfo {
     pub name: String,
     pub contents: Vec<u8>,
     pub hash: String,
+
+    /// The preview image to attach to the uploaded asset, if any.
+    pub preview: Option<AssetPreview>,
 }
 
 pub struct RobloxSyncBackend<'a> {
     api_client: &'a mut RobloxApiClient,
+    upload_to_user_id: Option<u64>,
     upload_to_group_id: Option<u64>,
 }
 
 impl<'a> RobloxSyncBackend<'a> {
-    pub fn new(api_client: &'a mut RobloxApiClient, upload_to_group_id: Option<u64>) -> Self {
+    pub fn new(
+        api_client: &'a mut RobloxApiClient,
+        upload_to_user_id: Option<u64>,
+        upload_to_group_id: Option<u64>,
+    ) -> Self {
         Self {
             api_client,
+            upload_to_user_id,
             upload_to_group_id,
         }
     }
@@ -48,9 +73,10 @@ impl<'a> SyncBackend for RobloxSyncBackend<'a> {
                 IMAGE.to_string(),
                 data.name.to_string(),
                 "Uploaded by Tarmac.".to_string(),
-                None,
+                self.upload_to_user_id,
                 self.upload_to_group_id,
-            )?,
+            )?
+            .with_preview(data.preview),
         };
 
         let result = self
@@ -63,13 +89,27 @@ impl<'a> SyncBackend for RobloxSyncBackend<'a> {
 
                 Ok(UploadResponse {
                     id: response.asset_id,
+                    moderation_state: response.moderation_state,
                 })
             }
 
+            Err(RobloxApiError::AssetRejected { message, .. }) => Err(Error::Rejected {
+                name: data.name,
+                reason: message,
+            }),
+
+            Err(RobloxApiError::RateLimited { retry_after }) => Err(Error::RateLimited {
+                retry_after,
+                waited: Duration::ZERO,
+            }),
+
             Err(RobloxApiError::ResponseError {
                 status: StatusCode::TOO_MANY_REQUESTS,
                 ..
-            }) => Err(Error::RateLimited),
+            }) => Err(Error::RateLimited {
+                retry_after: None,
+                waited: Duration::ZERO,
+            }),
 
             Err(err) => Err(err.into()),
         }
@@ -86,11 +126,15 @@ impl SyncBackend for NoneSyncBackend {
 
 pub struct DebugSyncBackend {
     last_id: u64,
+    output_dir: PathBuf,
 }
 
 impl DebugSyncBackend {
-    pub fn new() -> Self {
-        Self { last_id: 0 }
+    pub fn new(output_dir: PathBuf) -> Self {
+        Self {
+            last_id: 0,
+            output_dir,
+        }
     }
 }
 
@@ -101,58 +145,125 @@ impl SyncBackend for DebugSyncBackend {
         self.last_id += 1;
         let id = self.last_id;
 
-        let path = Path::new(".tarmac-debug");
-        fs::create_dir_all(path)?;
+        fs::create_dir_all(&self.output_dir)?;
 
-        let file_path = path.join(id.to_string());
+        let file_path = self.output_dir.join(debug_file_name(id, &data));
         fs::write(&file_path, &data.contents)?;
 
-        Ok(UploadResponse { id })
+        Ok(UploadResponse {
+            id,
+            moderation_state: None,
+        })
     }
 }
 
+/// Names the file that `DebugSyncBackend` writes an upload to, like
+/// `3-icons_close.png`. The ID comes first so that files sort in upload order,
+/// and any characters in the name that might not be allowed in file names are
+/// replaced.
+fn debug_file_name(id: u64, data: &UploadInfo) -> String {
+    let name: String = data
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let extension = match FileFormat::sniff(&data.contents) {
+        Some(FileFormat::Jpeg) => "jpg",
+        _ => "png",
+    };
+
+    format!("{}-{}.{}", id, name, extension)
+}
+
 /// Performs the retry logic for rate limitation errors. The struct wraps a SyncBackend so that
 /// when a RateLimited error occurs, the thread sleeps for a moment and then tries to reupload the
 /// data.
 pub struct RetryBackend<InnerSyncBackend> {
     inner: InnerSyncBackend,
-    delay: Duration,
-    attempts: usize,
+    policy: RetryPolicy,
+
+    /// How long this backend has spent waiting to retry, across every upload
+    /// it's been asked to do.
+    total_waited: Duration,
 }
 
 impl<InnerSyncBackend> RetryBackend<InnerSyncBackend> {
     /// Creates a new backend from another SyncBackend. The max_retries parameter gives the number
     /// of times the backend will try again (so given 0, it acts just as the original SyncBackend).
-    /// The delay parameter provides the amount of time to wait between each upload attempt.
+    /// The delay parameter provides the minimum amount of time to wait between each upload
+    /// attempt. If the server asks for a longer wait, that is used instead.
     pub fn new(inner: InnerSyncBackend, max_retries: usize, delay: Duration) -> Self {
         Self {
             inner,
-            delay,
-            attempts: max_retries + 1,
+            policy: RetryPolicy::new(max_retries, delay),
+            total_waited: Duration::ZERO,
+        }
+    }
+
+    /// Caps how long to wait between attempts. When the server asks for a
+    /// longer wait than max_delay, the backend either waits max_delay and tries
+    /// again anyway, or gives up right away if fail_over_max_delay is set.
+    pub fn with_max_delay(self, max_delay: Duration, fail_over_max_delay: bool) -> Self {
+        Self {
+            policy: self.policy.with_max_delay(max_delay, fail_over_max_delay),
+            ..self
         }
     }
+
+    /// Computes how long to wait before the next attempt, given how long the
+    /// server asked us to wait.
+    fn next_delay(&self, retry_after: Option<Duration>) -> Result<Duration, Error> {
+        self.policy
+            .next_delay(1, retry_after)
+            .ok_or_else(|| Error::RetryDelayTooLong {
+                requested: self.policy.requested_delay(1, retry_after),
+                max_delay: self.policy.max_delay(),
+            })
+    }
 }
 
 impl<InnerSyncBackend: SyncBackend> SyncBackend for RetryBackend<InnerSyncBackend> {
     fn upload(&mut self, data: UploadInfo) -> Result<UploadResponse, Error> {
-        for index in 0..self.attempts {
+        let mut retry_after = None;
+
+        for index in 0..self.policy.attempts() {
             if index != 0 {
+                let delay = self.next_delay(retry_after)?;
+
                 log::info!(
-                    "tarmac is being rate limited, retrying upload ({}/{})",
+                    "tarmac is being rate limited, retrying upload in {}s ({}/{}, {}s spent waiting so far)",
+                    delay.as_secs(),
                     index,
-                    self.attempts - 1
+                    self.policy.attempts() - 1,
+                    self.total_waited.as_secs()
                 );
-                thread::sleep(self.delay);
+                thread::sleep(delay);
+                self.total_waited += delay;
             }
             let result = self.inner.upload(data.clone());
 
+            // Only rate limits are worth waiting out. Rejected assets, like
+            // ones that were moderated, would be rejected again.
             match result {
-                Err(Error::RateLimited) => {}
+                Err(Error::RateLimited {
+                    retry_after: requested,
+                    ..
+                }) => retry_after = requested,
                 _ => return result,
             }
         }
 
-        Err(Error::RateLimited)
+        Err(Error::RateLimited {
+            retry_after,
+            waited: self.total_waited,
+        })
     }
 }
 
@@ -161,8 +272,30 @@ pub enum Error {
     #[error("Cannot upload assets with the 'none' target.")]
     NoneBackend,
 
-    #[error("Tarmac was rate-limited trying to upload assets. Try again in a little bit.")]
-    RateLimited,
+    #[error(
+        "Tarmac was rate-limited trying to upload assets{}. Try again in a little bit.",
+        describe_wait(.waited)
+    )]
+    RateLimited {
+        retry_after: Option<Duration>,
+
+        /// How long Tarmac had already spent waiting to retry uploads before
+        /// giving up.
+        waited: Duration,
+    },
+
+    #[error(
+        "Tarmac was rate-limited and asked to wait {}s, which is longer than the maximum of {}s.",
+        .requested.as_secs(),
+        .max_delay.as_secs()
+    )]
+    RetryDelayTooLong {
+        requested: Duration,
+        max_delay: Duration,
+    },
+
+    #[error("{name} was rejected by Roblox and won't be accepted if uploaded again: {reason}")]
+    Rejected { name: String, reason: String },
 
     #[error(transparent)]
     Io {
@@ -171,21 +304,52 @@ pub enum Error {
     },
 
     #[error(transparent)]
-    RobloxError {
+    RobloxApi {
         #[from]
         source: RobloxApiError,
     },
     #[error(transparent)]
-    RobloxAuthenticationError {
+    RobloxAuthentication {
         #[from]
         source: RobloxAuthenticationError,
     },
 }
 
+fn describe_wait(waited: &Duration) -> String {
+    if waited.is_zero() {
+        String::new()
+    } else {
+        format!(", after waiting {}s in total to retry", waited.as_secs())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn debug_backend_names_files_after_assets() {
+        let output_dir =
+            std::env::temp_dir().join(format!("tarmac-debug-test-{}", std::process::id()));
+        let mut backend = DebugSyncBackend::new(output_dir.clone());
+
+        let upload = |name: &str| UploadInfo {
+            name: name.to_owned(),
+            contents: b"\x89PNG\r\n\x1a\n".to_vec(),
+            hash: "hash".to_owned(),
+            preview: None,
+        };
+
+        let first = backend.upload(upload("spritesheet")).unwrap();
+        let second = backend.upload(upload("icons/Close button")).unwrap();
+
+        assert_eq!((first.id, second.id), (1, 2));
+        assert!(output_dir.join("1-spritesheet.png").is_file());
+        assert!(output_dir.join("2-icons_Close_button.png").is_file());
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
     #[allow(unused_must_use)]
     mod test_retry_backend {
         use super::*;
@@ -222,6 +386,14 @@ mod test {
                 name: "foo".to_owned(),
                 contents: Vec::new(),
                 hash: "hash".to_owned(),
+                preview: None,
+            }
+        }
+
+        fn rate_limited() -> Error {
+            Error::RateLimited {
+                retry_after: None,
+                waited: Duration::ZERO,
             }
         }
 
@@ -244,8 +416,8 @@ mod test {
         fn upload_again_if_rate_limited() {
             let mut counter = 0;
             let inner = CountUploads::new(&mut counter).with_results(vec![
-                Err(Error::RateLimited),
-                Err(Error::RateLimited),
+                Err(rate_limited()),
+                Err(rate_limited()),
                 Err(Error::NoneBackend),
             ]);
             let mut backend = RetryBackend::new(inner, 5, retry_duration());
@@ -258,10 +430,13 @@ mod test {
         #[test]
         fn upload_returns_first_success_result() {
             let mut counter = 0;
-            let success = UploadResponse { id: 10 };
+            let success = UploadResponse {
+                id: 10,
+                moderation_state: None,
+            };
             let inner = CountUploads::new(&mut counter).with_results(vec![
-                Err(Error::RateLimited),
-                Err(Error::RateLimited),
+                Err(rate_limited()),
+                Err(rate_limited()),
                 Ok(success.clone()),
             ]);
             let mut backend = RetryBackend::new(inner, 5, retry_duration());
@@ -272,24 +447,133 @@ mod test {
             assert_eq!(upload_result, success);
         }
 
+        #[test]
+        fn upload_does_not_retry_rejected_assets() {
+            let mut counter = 0;
+            let inner = CountUploads::new(&mut counter).with_results(vec![
+                Err(Error::Rejected {
+                    name: "foo".to_owned(),
+                    reason: "Asset is moderated".to_owned(),
+                }),
+                Ok(UploadResponse {
+                    id: 10,
+                    moderation_state: None,
+                }),
+            ]);
+            let mut backend = RetryBackend::new(inner, 5, retry_duration());
+
+            let upload_result = backend.upload(any_upload_info()).unwrap_err();
+
+            assert_eq!(counter, 1);
+            assert!(matches!(upload_result, Error::Rejected { .. }));
+        }
+
         #[test]
         fn upload_returns_rate_limited_when_retries_exhausted() {
             let mut counter = 0;
             let inner = CountUploads::new(&mut counter).with_results(vec![
-                Err(Error::RateLimited),
-                Err(Error::RateLimited),
-                Err(Error::RateLimited),
-                Err(Error::RateLimited),
+                Err(rate_limited()),
+                Err(rate_limited()),
+                Err(rate_limited()),
+                Err(rate_limited()),
             ]);
             let mut backend = RetryBackend::new(inner, 2, retry_duration());
 
             let upload_result = backend.upload(any_upload_info()).unwrap_err();
 
             assert_eq!(counter, 3);
-            assert!(match upload_result {
-                Error::RateLimited => true,
-                _ => false,
-            });
+            assert!(matches!(upload_result, Error::RateLimited { .. }));
+        }
+
+        #[test]
+        fn rate_limited_error_includes_total_wait() {
+            let mut counter = 0;
+            let inner = CountUploads::new(&mut counter).with_results(vec![
+                Err(rate_limited()),
+                Err(rate_limited()),
+                Err(rate_limited()),
+            ]);
+            let mut backend = RetryBackend::new(inner, 2, retry_duration());
+
+            match backend.upload(any_upload_info()).unwrap_err() {
+                Error::RateLimited { waited, .. } => assert_eq!(waited, retry_duration() * 2),
+                other => panic!("expected RateLimited, got {:?}", other),
+            }
+        }
+
+        #[test]
+        fn delay_uses_server_retry_after_when_longer() {
+            let mut counter = 0;
+            let backend = RetryBackend::new(CountUploads::new(&mut counter), 1, retry_duration());
+
+            let delay = backend.next_delay(Some(Duration::from_secs(5))).unwrap();
+
+            assert_eq!(delay, Duration::from_secs(5));
+        }
+
+        #[test]
+        fn delay_is_at_least_the_configured_delay() {
+            let mut counter = 0;
+            let backend =
+                RetryBackend::new(CountUploads::new(&mut counter), 1, Duration::from_secs(10));
+
+            assert_eq!(
+                backend.next_delay(Some(Duration::from_secs(1))).unwrap(),
+                Duration::from_secs(10)
+            );
+            assert_eq!(backend.next_delay(None).unwrap(), Duration::from_secs(10));
+        }
+
+        #[test]
+        fn delay_is_capped_at_max_delay() {
+            let mut counter = 0;
+            let backend = RetryBackend::new(CountUploads::new(&mut counter), 1, retry_duration())
+                .with_max_delay(Duration::from_secs(60), false);
+
+            let delay = backend.next_delay(Some(Duration::from_secs(3600))).unwrap();
+
+            assert_eq!(delay, Duration::from_secs(60));
+        }
+
+        #[test]
+        fn upload_retries_after_waiting_max_delay() {
+            let mut counter = 0;
+            let inner = CountUploads::new(&mut counter).with_results(vec![
+                Err(Error::RateLimited {
+                    retry_after: Some(Duration::from_secs(3600)),
+                    waited: Duration::ZERO,
+                }),
+                Ok(UploadResponse {
+                    id: 10,
+                    moderation_state: None,
+                }),
+            ]);
+            let mut backend = RetryBackend::new(inner, 1, retry_duration())
+                .with_max_delay(retry_duration(), false);
+
+            let upload_result = backend.upload(any_upload_info());
+
+            assert_eq!(counter, 2);
+            assert!(upload_result.is_ok());
+        }
+
+        #[test]
+        fn upload_fails_fast_when_retry_after_exceeds_max_delay() {
+            let mut counter = 0;
+            let inner = CountUploads::new(&mut counter).with_results(vec![
+                Err(Error::RateLimited {
+                    retry_after: Some(Duration::from_secs(3600)),
+                    waited: Duration::ZERO,
+                }),
+                Err(Error::NoneBackend),
+            ]);
+            let mut backend = RetryBackend::new(inner, 5, retry_duration())
+                .with_max_delay(Duration::from_secs(60), true);
+
+            let upload_result = backend.upload(any_upload_info()).unwrap_err();
+
+            assert_eq!(counter, 1);
+            assert!(matches!(upload_result, Error::RetryDelayTooLong { .. }));
         }
     }
 }
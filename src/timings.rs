@@ -0,0 +1,132 @@
+//! Optional instrumentation for finding out where time goes during a sync.
+//!
+//! Durations are recorded for each asset at each stage of the sync pipeline,
+//! then summarized once the sync is done.
+
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    fmt::{self, Write},
+    time::Duration,
+};
+
+/// A stage of the sync pipeline that Tarmac records durations for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Read,
+    Hash,
+    Decode,
+    Pack,
+    Bleed,
+    Upload,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Stage::Read => "read",
+            Stage::Hash => "hash",
+            Stage::Decode => "decode",
+            Stage::Pack => "pack",
+            Stage::Bleed => "bleed",
+            Stage::Upload => "upload",
+        };
+
+        formatter.pad(name)
+    }
+}
+
+#[derive(Debug)]
+struct TimingEntry {
+    subject: String,
+    stage: Stage,
+    duration: Duration,
+}
+
+/// Collects how long each stage took for each asset.
+#[derive(Debug, Default)]
+pub struct Timings {
+    entries: Vec<TimingEntry>,
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `stage` took `duration` for the given subject, usually the
+    /// name of an asset.
+    pub fn record(&mut self, subject: impl Into<String>, stage: Stage, duration: Duration) {
+        self.entries.push(TimingEntry {
+            subject: subject.into(),
+            stage,
+            duration,
+        });
+    }
+
+    /// Creates a human-readable breakdown of the recorded durations: first the
+    /// total for each stage, then every recorded duration. Both are sorted
+    /// with the slowest first.
+    pub fn report(&self) -> String {
+        let mut totals: BTreeMap<Stage, Duration> = BTreeMap::new();
+        for entry in &self.entries {
+            *totals.entry(entry.stage).or_default() += entry.duration;
+        }
+
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by_key(|&(_, duration)| Reverse(duration));
+
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by_key(|entry| Reverse(entry.duration));
+
+        let mut report = String::new();
+
+        writeln!(report, "Time per stage:").unwrap();
+        for (stage, duration) in totals {
+            writeln!(report, "  {:<8}{:>10.3}s", stage, duration.as_secs_f64()).unwrap();
+        }
+
+        writeln!(report, "Time per asset:").unwrap();
+        for entry in entries {
+            writeln!(
+                report,
+                "  {:<8}{:>10.3}s  {}",
+                entry.stage,
+                entry.duration.as_secs_f64(),
+                entry.subject
+            )
+            .unwrap();
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn report_is_sorted_slowest_first() {
+        let mut timings = Timings::new();
+        timings.record("a", Stage::Decode, Duration::from_millis(10));
+        timings.record("b", Stage::Upload, Duration::from_millis(500));
+        timings.record("c", Stage::Decode, Duration::from_millis(20));
+
+        let report = timings.report();
+        let lines: Vec<_> = report.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "Time per stage:",
+                "  upload       0.500s",
+                "  decode       0.030s",
+                "Time per asset:",
+                "  upload       0.500s  b",
+                "  decode       0.020s  c",
+                "  decode       0.010s  a",
+            ]
+        );
+    }
+}